@@ -1,35 +1,91 @@
-use std::io;
+use std::{io, iter, ops::Range, path::PathBuf, time::Duration};
 
+use clap::ValueEnum;
 use ratatui::{
     backend::Backend,
     crossterm,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::Widget,
     Frame, Terminal,
 };
 use ratatui_textarea::{Input, Key};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    banners::{footer, header, help},
-    body::{captures, substitution, TestInput},
-    regex_input::{RegexInput, SubstitutionInput},
+    banners::{footer, header, help, Status},
+    bench::{report, timings, variants, DEFAULT_RUNS},
+    body::{
+        captures, captures_table, extract, extracted, fields, input_title, match_detail,
+        substitution, Highlight, TestInput,
+    },
+    clipboard::{copy, CopyMenu, Target, TARGETS},
+    engine::{Captures, Engine},
+    explain::explain,
+    export::ExportMenu,
+    filter::{filtered, printed, with_context, FilterOptions},
+    flags::FLAGS,
+    history::{history_search, Entry, History},
+    library::{library_browser, Library},
+    picker::{Picked, Picker},
+    regex_input::{
+        CasesInput, ContextInput, GroupInput, LimitInput, PatternsInput, RegexInput,
+        SubstitutionInput,
+    },
+    session::Session,
+    set::{classified, hits},
+    suite::{check_cases, results, Suite},
+    worker::{Job, Output, Results, Worker},
 };
 
-#[derive(Clone, Copy)]
-enum Mode {
+/// How long to wait for the results before drawing, most searches are done by then.
+const WAIT: Duration = Duration::from_millis(30);
+
+/// How often the spinner moves while matching.
+const TICK: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
     Match,
+    #[default]
     Substitution,
+    Split,
+    Filter,
+    Extract,
+    Set,
+    Bench,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 enum EditMode {
     Regex,
     Substitution,
+    Limit,
+    Context,
+    Group,
     Body,
+    Cases,
 }
 
-enum InfoMode {
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InfoMode {
     QuickReference,
+    #[default]
     Captures,
+    /// The selected match in detail.
+    Match,
+    Explain,
+    Tests,
+}
+
+pub struct InitialState {
+    pub session: Session,
+    /// Where ^s saves the test suite.
+    pub tests_path: PathBuf,
+    /// Where alt+w saves the session.
+    pub session_path: PathBuf,
+    pub history: History,
 }
 
 enum Action {
@@ -42,158 +98,1002 @@ pub struct App<'a> {
     mode: Mode,
     edit_mode: EditMode,
     info_mode: InfoMode,
+    highlight: Highlight,
     regex_input: RegexInput<'a>,
+    patterns_input: PatternsInput<'a>,
+    /// The patterns were given or edited, not only seeded from the regex.
+    patterns_edited: bool,
     sub_input: SubstitutionInput<'a>,
+    limit_input: LimitInput<'a>,
+    context_input: ContextInput<'a>,
+    group_input: GroupInput<'a>,
+    nul_separated: bool,
+    invert: bool,
+    line_numbers: bool,
     body: TestInput<'a>,
+    cases_input: CasesInput<'a>,
+    tests_path: PathBuf,
+    session_path: PathBuf,
+    /// Shown in the footer until the next key press.
+    message: Option<String>,
+    history: History,
+    /// Steps back in the history while recalling with Up and Down.
+    recalled: Option<usize>,
+    /// The pattern being edited before recalling, restored by going past the newest entry.
+    draft: Option<Entry>,
+    search: Option<Picker<'a>>,
+    library: Library,
+    browser: Option<Picker<'a>>,
+    export: Option<ExportMenu>,
+    copy_menu: Option<CopyMenu>,
+    /// Text to put on the clipboard once the key is handled.
+    clipboard: Option<String>,
+    /// Index of the match stepped to with alt+n and alt+p.
+    selected: Option<usize>,
+    /// Timed runs of every pattern in bench mode.
+    runs: usize,
+    worker: Worker,
 }
 
 impl App<'_> {
-    pub fn new(prefill_input: Option<Vec<String>>) -> Self {
-        let mut body = TestInput::new();
-        if let Some(input) = prefill_input {
-            for line in &input {
-                body.textarea.insert_str(line);
-                body.textarea.insert_newline();
-            }
+    pub fn new(state: InitialState) -> Self {
+        let session = state.session;
+        let mut app = Self {
+            mode: session.mode,
+            edit_mode: EditMode::Regex,
+            info_mode: session.info_mode,
+            highlight: session.highlight,
+            patterns_edited: !session.patterns.is_empty(),
+            patterns_input: PatternsInput::new(
+                match session.patterns.is_empty() {
+                    true => vec![session.regex.clone()],
+                    false => session.patterns,
+                },
+                &session.flags,
+                match session.mode {
+                    Mode::Set => Engine::Regex,
+                    _ => session.engine,
+                },
+            ),
+            regex_input: RegexInput::new(session.regex, session.flags, session.engine),
+            sub_input: SubstitutionInput::new(session.substitution),
+            limit_input: LimitInput::new(session.limit),
+            context_input: ContextInput::new(session.context),
+            group_input: GroupInput::new(session.group),
+            cases_input: CasesInput::new(session.cases),
+            tests_path: state.tests_path,
+            session_path: state.session_path,
+            message: None,
+            history: state.history,
+            recalled: None,
+            draft: None,
+            search: None,
+            library: Library::load(),
+            browser: None,
+            export: None,
+            copy_menu: None,
+            clipboard: None,
+            selected: None,
+            runs: session.runs.unwrap_or(DEFAULT_RUNS),
+            worker: Worker::new(),
+            nul_separated: session.nul_separated,
+            invert: session.invert,
+            line_numbers: session.line_numbers,
+            body: TestInput::new(session.body),
+        };
+        if let Mode::Bench = app.mode {
+            app.lead_with_regex();
         }
+        app
+    }
 
-        Self {
-            mode: Mode::Substitution,
-            edit_mode: EditMode::Regex,
-            info_mode: InfoMode::Captures,
-            regex_input: RegexInput::new(),
-            sub_input: SubstitutionInput::new(),
-            body,
+    fn session(&self) -> Session {
+        Session {
+            mode: self.mode,
+            info_mode: self.info_mode,
+            highlight: self.highlight,
+            regex: self.regex_input.textarea.lines()[0].clone(),
+            flags: self.regex_input.flags,
+            engine: self.regex_input.engine,
+            substitution: self.sub_input.current_substitution(),
+            limit: self.limit_input.current_limit(),
+            nul_separated: self.nul_separated,
+            context: self.context_input.current_context(),
+            invert: self.invert,
+            line_numbers: self.line_numbers,
+            group: self.group_input.current_group().to_string(),
+            // Left out when only seeded, so they are seeded from the regex again on load.
+            patterns: match self.patterns_edited || matches!(self.mode, Mode::Set | Mode::Bench) {
+                true => self.patterns_input.textarea.lines().to_vec(),
+                false => vec![],
+            },
+            runs: Some(self.runs),
+            cases: self.cases_input.textarea.lines().to_vec(),
+            body: self.body.body().to_string(),
         }
     }
 
-    pub fn run<B: Backend<Error = io::Error>>(&mut self, term: &mut Terminal<B>) -> io::Result<Option<String>> {
+    fn save_session(&mut self) {
+        self.remember();
+        self.message = Some(match self.session().save(&self.session_path) {
+            Ok(()) => format!("session saved to {}", self.session_path.display()),
+            Err(err) => format!("session not saved, {}", err),
+        });
+    }
+
+    pub fn run<B: Backend<Error = io::Error> + io::Write>(
+        &mut self,
+        term: &mut Terminal<B>,
+    ) -> io::Result<Option<String>> {
         loop {
+            self.search();
+            // The focused input has no highlighting but still shows the selected match.
+            let matches = self.found().len();
+            let selected = self.selected.filter(|i| *i < matches);
+            self.body
+                .set_title(input_title(self.highlight, selected.map(|i| (i, matches))));
             term.draw(|f| self.draw(f))?;
-            match self.handle_input()? {
+            // Keep drawing the spinner while matching, only then is a key not waited for.
+            if self.worker.running().is_some() && !crossterm::event::poll(TICK)? {
+                continue;
+            }
+            let action = self.handle_input()?;
+            if let Some(text) = self.clipboard.take() {
+                copy(term.backend_mut(), &text)?;
+            }
+            match action {
                 Action::Quit => return Ok(None),
                 Action::ReturnValue(s) => return Ok(Some(s)),
                 Action::Continue => {}
             }
         }
     }
+    /// Start matching when anything the results depend on changed, and take the results
+    /// once they are in.
+    fn search(&mut self) {
+        let patterns = matches!(self.mode, Mode::Set | Mode::Bench);
+        let bench = matches!(self.mode, Mode::Bench);
+        let job = Job {
+            regex: match patterns {
+                true => String::new(),
+                false => self.regex_input.current_regex_str(),
+            },
+            engine: self.regex_input.engine,
+            version: self.body.version(),
+            mode: self.mode,
+            substitution: self.sub_input.current_substitution(),
+            limit: self.limit_input.current_limit(),
+            invert: self.invert,
+            patterns: match patterns {
+                true => self.patterns_input.textarea.lines().to_vec(),
+                false => vec![],
+            },
+            flags: self.regex_input.flags.inline(),
+            runs: if bench { self.runs } else { 0 },
+        };
+        if !self.worker.is_current(&job) {
+            let regex = self
+                .regex_input
+                .current_regex()
+                .filter(|_| !patterns)
+                .cloned();
+            let set = match self.mode {
+                Mode::Set => self.patterns_input.current_set().cloned(),
+                _ => None,
+            };
+            let variants = match bench {
+                true => variants(
+                    self.patterns_input.textarea.lines(),
+                    &self.regex_input.flags,
+                    self.regex_input.engine,
+                ),
+                false => vec![],
+            };
+            self.worker
+                .start(job, regex, set, variants, self.body.shared());
+        }
+        match self.worker.receive(WAIT) {
+            Some(Results {
+                error: Some(err), ..
+            }) => self.message = Some(err.clone()),
+            Some(results) if results.timed_out && matches!(self.mode, Mode::Set) => {
+                self.message = Some(format!(
+                    "matching stopped after {:.1}s",
+                    results.elapsed.as_secs_f64()
+                ));
+            }
+            Some(results) if results.timed_out => {
+                let count = results.captures.len();
+                self.message = Some(format!(
+                    "matching stopped after {:.1}s, showing the first {} match{}",
+                    results.elapsed.as_secs_f64(),
+                    count,
+                    if count == 1 { "" } else { "es" }
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    /// Diagnostics of the regex for the footer, none when it is invalid.
+    fn status(&self) -> Option<Status<'_>> {
+        self.regex_input.current_regex()?;
+        let results = self.worker.results();
+        Some(Status {
+            matches: self.found().len(),
+            captures: self
+                .found()
+                .iter()
+                .map(|caps| caps.iter().skip(1).flatten().count())
+                .sum(),
+            partial: results.is_some_and(|results| results.timed_out),
+            compile_time: self.regex_input.compile_time(),
+            search_time: results.map(|results| results.elapsed),
+            program: self.regex_input.program(),
+        })
+    }
+
+    /// Every match in the input, none while matching.
+    fn found(&self) -> &[Captures] {
+        self.worker
+            .results()
+            .map_or(&[], |results| results.captures.as_slice())
+    }
+
     fn draw(&self, f: &mut Frame) {
         match self.mode {
             Mode::Match => self.draw_match(f),
             Mode::Substitution => self.draw_substitution(f),
+            Mode::Split => self.draw_split(f),
+            Mode::Filter => self.draw_filter(f),
+            Mode::Extract => self.draw_extract(f),
+            Mode::Set => self.draw_set(f),
+            Mode::Bench => self.draw_bench(f),
+        }
+        let area = f.area();
+        let popup = Rect::new(
+            area.width / 8,
+            area.height / 4,
+            area.width * 3 / 4,
+            area.height / 2,
+        );
+        if let Some(search) = &self.search {
+            f.render_widget(self.history.picker(search), popup);
+        }
+        if let Some(browser) = &self.browser {
+            f.render_widget(self.library.picker(browser), popup);
+        }
+        if let Some(export) = &self.export {
+            f.render_widget(export.widget(), popup);
+        }
+        if let Some(menu) = &self.copy_menu {
+            let height = TARGETS.len() as u16 + 2;
+            let menu_area = Rect::new(
+                popup.x,
+                popup.y,
+                popup.width.min(40),
+                popup.height.min(height),
+            );
+            f.render_widget(menu.widget(), menu_area);
         }
     }
 
-    fn draw_match(&self, f: &mut Frame) {
+    fn filter_options(&self) -> FilterOptions {
+        FilterOptions {
+            invert: self.invert,
+            line_numbers: self.line_numbers,
+            context: self.context_input.current_context(),
+        }
+    }
+
+    /// The regex input, only focused while editing the regex.
+    fn draw_regex(&self, f: &mut Frame, area: Rect) {
+        match self.edit_mode {
+            EditMode::Regex => f.render_widget(&self.regex_input, area),
+            _ => f.render_widget(self.regex_input.unfocused(), area),
+        }
+    }
+
+    fn draw_info(&self, f: &mut Frame, area: Rect) {
+        match self.info_mode {
+            InfoMode::QuickReference => f.render_widget(help(self.regex_input.engine), area),
+            InfoMode::Captures => f.render_widget(
+                captures(
+                    self.regex_input.current_regex(),
+                    self.body.body(),
+                    self.found(),
+                    self.highlight,
+                ),
+                area,
+            ),
+            InfoMode::Match => f.render_widget(
+                match_detail(
+                    self.regex_input.current_regex(),
+                    self.body.body(),
+                    self.found(),
+                    self.current_match(),
+                ),
+                area,
+            ),
+            InfoMode::Explain => f.render_widget(
+                explain(
+                    &self.regex_input.textarea.lines()[0],
+                    &self.regex_input.flags,
+                    self.regex_input.engine,
+                ),
+                area,
+            ),
+            InfoMode::Tests => {
+                let layout = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(vec![Constraint::Fill(1), Constraint::Fill(1)])
+                    .split(area);
+                match self.edit_mode {
+                    EditMode::Cases => f.render_widget(&self.cases_input.textarea, layout[0]),
+                    _ => f.render_widget(self.cases_input.unfocused(), layout[0]),
+                }
+                f.render_widget(
+                    results(
+                        self.cases_input.textarea.lines(),
+                        self.regex_input.current_regex(),
+                        self.cases_input.saved.as_deref(),
+                    ),
+                    layout[1],
+                );
+            }
+        }
+    }
+
+    /// Passed and total test cases for the header, none without cases.
+    fn tests_summary(&self) -> Option<(usize, usize)> {
+        let outcomes = check_cases(
+            self.cases_input.textarea.lines(),
+            self.regex_input.current_regex(),
+        );
+        let passed = outcomes.iter().filter(|(_, o)| o.is_ok()).count();
+        (!outcomes.is_empty()).then_some((passed, outcomes.len()))
+    }
+
+    fn current_entry(&self) -> Entry {
+        Entry::new(
+            self.regex_input.textarea.lines()[0].clone(),
+            self.regex_input.flags,
+            self.regex_input.engine,
+            self.sub_input.current_substitution(),
+        )
+    }
+
+    /// Add the current pattern to the history. This is best effort, failing to write the
+    /// history should not keep the output from being exported.
+    fn remember(&mut self) {
+        let _ = self.history.record(self.current_entry());
+    }
+
+    fn recall(&mut self, entry: Entry) {
+        self.regex_input = RegexInput::new(entry.regex, entry.flags, entry.engine);
+        self.sub_input = SubstitutionInput::new(entry.substitution);
+        self.validate_patterns();
+    }
+
+    /// Put the regex at the top of the patterns unless it is there already, bench mode
+    /// compares the other patterns to the first.
+    fn lead_with_regex(&mut self) {
+        let regex = &self.regex_input.textarea.lines()[0];
+        let patterns = self.patterns_input.textarea.lines();
+        if regex.is_empty() || patterns[0] == *regex {
+            return;
+        }
+        let patterns = match patterns {
+            [only] if only.is_empty() => vec![regex.clone()],
+            patterns => iter::once(regex.clone())
+                .chain(patterns.iter().cloned())
+                .collect(),
+        };
+        self.patterns_input =
+            PatternsInput::new(patterns, &self.regex_input.flags, self.regex_input.engine);
+    }
+
+    /// Check the patterns with the engine they run on, sets only run on the regex crate.
+    fn validate_patterns(&mut self) {
+        let engine = match self.mode {
+            Mode::Set => Engine::Regex,
+            _ => self.regex_input.engine,
+        };
+        self.patterns_input
+            .validate(&self.regex_input.flags, engine);
+    }
+
+    /// Recall the entry before the recalled one, or the newest when not recalling yet.
+    /// Recall the next older entry of the history. Entries equal to the pattern being edited
+    /// are skipped, like the one recorded by the last export.
+    fn history_back(&mut self) {
+        let draft = match (self.recalled, &self.draft) {
+            (Some(_), Some(draft)) => draft.clone(),
+            _ => self.current_entry(),
+        };
+        let from = self.recalled.map_or(0, |back| back + 1);
+        let Some((back, entry)) = (from..)
+            .map_while(|back| Some((back, self.history.get(back)?)))
+            .find(|(_, entry)| !entry.same(&draft))
+        else {
+            return;
+        };
+        let entry = entry.clone();
+        self.draft = Some(draft);
+        self.recalled = Some(back);
+        self.recall(entry);
+    }
+
+    /// Recall the next newer entry of the history, skipping the same entries as going back,
+    /// or the pattern being edited past the newest one.
+    fn history_forward(&mut self) {
+        let (Some(recalled), Some(draft)) = (self.recalled, &self.draft) else {
+            return;
+        };
+        let newer = (0..recalled).rev().find_map(|back| {
+            let entry = self.history.get(back)?;
+            (!entry.same(draft)).then(|| (back, entry.clone()))
+        });
+        match newer {
+            Some((back, entry)) => {
+                self.recalled = Some(back);
+                self.recall(entry);
+            }
+            None => {
+                self.recalled = None;
+                if let Some(draft) = self.draft.take() {
+                    self.recall(draft);
+                }
+            }
+        }
+    }
+
+    fn handle_search(&mut self, input: Input) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+        let results = self.history.search(search.current_query()).len();
+        match search.input(input, results) {
+            Some(Picked::Cancel) => self.search = None,
+            Some(Picked::Item(selected)) => {
+                let entry = self
+                    .history
+                    .search(search.current_query())
+                    .get(selected)
+                    .map(|entry| (*entry).clone());
+                self.search = None;
+                if let Some(entry) = entry {
+                    self.recalled = None;
+                    self.recall(entry);
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn handle_browser(&mut self, input: Input) {
+        let Some(browser) = &mut self.browser else {
+            return;
+        };
+        let results = self.library.search(browser.current_query()).len();
+        match browser.input(input, results) {
+            Some(Picked::Cancel) => self.browser = None,
+            Some(Picked::Item(selected)) => {
+                let regex = self
+                    .library
+                    .search(browser.current_query())
+                    .get(selected)
+                    .map(|pattern| pattern.regex.clone());
+                self.browser = None;
+                if let Some(regex) = regex {
+                    self.insert_pattern(&regex);
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn open_export(&mut self) {
+        let (regexes, substitution) = match self.mode {
+            Mode::Set | Mode::Bench => (
+                self.patterns_input
+                    .textarea
+                    .lines()
+                    .iter()
+                    .filter(|pattern| !pattern.is_empty())
+                    .cloned()
+                    .collect(),
+                None,
+            ),
+            Mode::Substitution => (
+                vec![self.regex_input.textarea.lines()[0].clone()],
+                Some(self.sub_input.current_substitution()).filter(|sub| !sub.is_empty()),
+            ),
+            _ => (vec![self.regex_input.textarea.lines()[0].clone()], None),
+        };
+        self.export = Some(ExportMenu::new(
+            regexes,
+            self.regex_input.flags,
+            self.regex_input.engine,
+            substitution,
+        ));
+    }
+
+    fn handle_export(&mut self, input: Input) -> Action {
+        let Some(export) = &mut self.export else {
+            return Action::Continue;
+        };
+        match input {
+            Input { key: Key::Esc, .. } => self.export = None,
+            Input {
+                key: Key::Enter, ..
+            } => {
+                let code = export.current();
+                self.export = None;
+                self.remember();
+                return Action::ReturnValue(code);
+            }
+            Input {
+                key: Key::Char('c'),
+                ..
+            } => {
+                let (name, code) = (export.language().name(), export.current());
+                self.export = None;
+                self.remember();
+                self.copy(&format!("the {} export", name), code);
+            }
+            Input { key: Key::Up, .. } => export.up(),
+            Input { key: Key::Down, .. } => export.down(),
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    /// Insert a library pattern at the cursor of the regex, or of the patterns in set and
+    /// bench mode.
+    fn insert_pattern(&mut self, regex: &str) {
+        self.edit_mode = EditMode::Regex;
+        match self.mode {
+            Mode::Set | Mode::Bench => {
+                self.patterns_input.textarea.insert_str(regex);
+                self.patterns_edited = true;
+                self.validate_patterns();
+            }
+            _ => {
+                self.regex_input.textarea.insert_str(regex);
+                self.recalled = None;
+                self.regex_input.validate();
+            }
+        }
+    }
+
+    /// The regex as `^e` exports it, the patterns in set and bench mode.
+    fn exported_regex(&self) -> String {
+        match self.mode {
+            Mode::Set | Mode::Bench => self.patterns_input.textarea.lines().join("\n"),
+            _ => self.regex_input.current_regex_str(),
+        }
+    }
+
+    /// The output of the mode as `^o` exports it. This is only asked for on a key, so it is
+    /// computed here rather than waited for.
+    /// The output of the mode, made from the results of the worker. Fails while it is still
+    /// matching, or when it gave up or ran into the timeout before the output was done.
+    fn output(&self) -> Result<String, String> {
+        let results = match self.worker.results() {
+            Some(results) if self.worker.running().is_none() => results,
+            _ => return Err("still matching, try again when done".to_string()),
+        };
+        if let Some(error) = &results.error {
+            return Err(error.clone());
+        }
+        if results.timed_out {
+            return Err("matching timed out, there is no output".to_string());
+        }
+        let body = self.body.body();
+        let output = match (self.mode, &results.output) {
+            (Mode::Split, Output::Fields(fields)) => {
+                fields.join(if self.nul_separated { "\0" } else { "\n" })
+            }
+            (Mode::Filter, Output::Selected(selected)) => printed(
+                with_context(body, selected, self.filter_options()),
+                self.filter_options(),
+            ),
+            (Mode::Extract, _) => extract(
+                body,
+                self.regex_input.current_regex(),
+                &results.captures,
+                self.group_input.current_group(),
+            )
+            .unwrap_or_default()
+            .join("\n"),
+            (Mode::Set, Output::Set { hits, .. }) => classified(body, hits),
+            (Mode::Set, _) => classified(body, &vec![vec![]; body.lines().count()]),
+            (Mode::Bench, Output::Bench(measurements)) => report(measurements, body.len()),
+            (Mode::Match | Mode::Substitution, Output::Substituted(output)) => output.clone(),
+            _ => String::new(),
+        };
+        Ok(output)
+    }
+
+    fn matches(&self) -> Vec<Range<usize>> {
+        self.found()
+            .iter()
+            .filter_map(|caps| caps[0].clone())
+            .collect()
+    }
+
+    /// Index of the selected match, or else the match at or after the cursor in the input or
+    /// the last one before it.
+    fn current_match(&self) -> Option<usize> {
+        let matches = self.matches();
+        if let Some(selected) = self.selected.filter(|i| *i < matches.len()) {
+            return Some(selected);
+        }
+        let offset = self.body.cursor_offset();
+        matches
+            .iter()
+            .position(|range| range.end > offset)
+            .or(matches.len().checked_sub(1))
+    }
+
+    /// Select the next or previous match, starting from the cursor when none is selected,
+    /// and move the cursor to it.
+    fn step_match(&mut self, forward: bool) {
+        if let Mode::Set | Mode::Bench = self.mode {
+            self.message = Some(format!(
+                "{} mode has no matches to step through",
+                match self.mode {
+                    Mode::Set => "set",
+                    _ => "bench",
+                }
+            ));
+            return;
+        }
+        let matches = self.matches();
+        if matches.is_empty() {
+            self.message = Some(match self.worker.running() {
+                Some(_) => "still matching".to_string(),
+                None => "no matches".to_string(),
+            });
+            return;
+        }
+        let offset = self.body.cursor_offset();
+        let last = matches.len() - 1;
+        let index = match (self.selected.filter(|i| *i <= last), forward) {
+            (Some(i), true) => {
+                if i == last {
+                    0
+                } else {
+                    i + 1
+                }
+            }
+            (Some(i), false) => {
+                if i == 0 {
+                    last
+                } else {
+                    i - 1
+                }
+            }
+            (None, true) => matches
+                .iter()
+                .position(|range| range.start >= offset)
+                .unwrap_or(0),
+            (None, false) => matches
+                .iter()
+                .rposition(|range| range.start < offset)
+                .unwrap_or(last),
+        };
+        self.selected = Some(index);
+        self.body.jump_to(matches[index].start);
+        if !matches!(self.edit_mode, EditMode::Cases) {
+            self.info_mode = InfoMode::Match;
+        }
+    }
+
+    /// Queue the text for the clipboard, it is written after the key is handled.
+    fn copy(&mut self, what: &str, text: String) {
+        self.message = Some(format!(
+            "copied {} to the clipboard, {} byte{}",
+            what,
+            text.len(),
+            if text.len() == 1 { "" } else { "s" }
+        ));
+        self.clipboard = Some(text);
+    }
+
+    fn handle_copy(&mut self, input: Input) {
+        let Some(menu) = &mut self.copy_menu else {
+            return;
+        };
+        match input {
+            Input { key: Key::Esc, .. } => self.copy_menu = None,
+            Input {
+                key: Key::Enter, ..
+            } => {
+                let target = menu.current();
+                self.copy_menu = None;
+                let text = match target {
+                    Target::Regex => Some(self.exported_regex()),
+                    Target::Output => match self.output() {
+                        Ok(output) => Some(output),
+                        Err(err) => {
+                            self.message = Some(err);
+                            return;
+                        }
+                    },
+                    Target::Captures => self
+                        .regex_input
+                        .current_regex()
+                        .map(|reg| captures_table(reg, self.body.body(), self.found())),
+                    Target::Match => self
+                        .current_match()
+                        .map(|i| self.body.body()[self.matches()[i].clone()].to_string()),
+                };
+                match text {
+                    Some(text) => self.copy(target.name(), text),
+                    None => self.message = Some(format!("no {} to copy", target.name())),
+                }
+            }
+            Input { key: Key::Up, .. } => menu.up(),
+            Input { key: Key::Down, .. } => menu.down(),
+            _ => {}
+        }
+    }
+
+    fn save_tests(&mut self) {
+        self.remember();
+        let saved = self.cases_input.cases().and_then(|cases| {
+            let suite = Suite {
+                regex: self.regex_input.textarea.lines()[0].clone(),
+                flags: self.regex_input.flags,
+                engine: self.regex_input.engine,
+                cases,
+            };
+            suite.save(&self.tests_path).map_err(|err| err.to_string())
+        });
+        self.cases_input.saved = Some(match saved {
+            Ok(()) => format!("saved to {}", self.tests_path.display()),
+            Err(err) => format!("not saved, {}", err),
+        });
+    }
+
+    /// Draw the header, the regex with `field` next to it, the input, the info pane and the
+    /// footer. With a field there is room for the output of the mode, its area is returned.
+    fn draw_frame(&self, f: &mut Frame, field: Option<EditMode>) -> Option<Rect> {
+        let mut constraints = vec![
+            Constraint::Length(2),
+            Constraint::Length(3),
+            Constraint::Length(self.regex_input.status_height()),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Length(2),
+        ];
+        if field.is_some() {
+            constraints.insert(4, Constraint::Fill(1));
+        }
         let layout = Layout::default()
             .direction(Direction::Vertical)
-            .constraints(vec![
-                Constraint::Length(2),
-                Constraint::Length(3),
-                Constraint::Fill(1),
-                Constraint::Fill(1),
-                Constraint::Length(1),
-            ])
+            .constraints(constraints)
             .split(f.area());
-        f.render_widget(header(), layout[0]);
-        f.render_widget(footer(), layout[4]);
+        let (info_area, footer_area) = (layout[layout.len() - 2], layout[layout.len() - 1]);
+        f.render_widget(
+            header(
+                self.regex_input.engine,
+                self.tests_summary(),
+                self.worker.running(),
+            ),
+            layout[0],
+        );
+        f.render_widget(footer(self.message.as_deref(), self.status()), footer_area);
+        f.render_widget(self.regex_input.status(), layout[2]);
 
+        let regex_area = match field {
+            Some(field) => {
+                let input_layout = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(vec![Constraint::Fill(1), Constraint::Fill(1)])
+                    .split(layout[1]);
+                self.draw_field(f, field, input_layout[1]);
+                input_layout[0]
+            }
+            None => layout[1],
+        };
         match self.edit_mode {
             EditMode::Body => {
-                f.render_widget(self.regex_input.unfocused(), layout[1]);
-                f.render_widget(&self.body.textarea, layout[2]);
+                f.render_widget(self.regex_input.unfocused(), regex_area);
+                f.render_widget(&self.body.textarea, layout[3]);
             }
             _ => {
-                f.render_widget(&self.regex_input.textarea, layout[1]);
+                self.draw_regex(f, regex_area);
                 f.render_widget(
-                    self.body.highlighted_body(self.regex_input.current_regex()),
-                    layout[2],
+                    self.body
+                        .highlighted_body(self.found(), self.highlight, self.selected),
+                    layout[3],
                 );
             }
         }
 
-        match self.info_mode {
-            InfoMode::QuickReference => f.render_widget(help(), layout[3]),
-            InfoMode::Captures => f.render_widget(
-                captures(self.regex_input.current_regex(), self.body.body()),
-                layout[3],
-            ),
+        self.draw_info(f, info_area);
+        field.map(|_| layout[4])
+    }
+
+    /// The field next to the regex, only focused while editing it.
+    fn draw_field(&self, f: &mut Frame, field: EditMode, area: Rect) {
+        let focused = self.edit_mode == field;
+        match field {
+            EditMode::Substitution if focused => f.render_widget(&self.sub_input.textarea, area),
+            EditMode::Substitution => f.render_widget(self.sub_input.unfocused(), area),
+            EditMode::Limit if focused => f.render_widget(&self.limit_input.textarea, area),
+            EditMode::Limit => f.render_widget(self.limit_input.unfocused(), area),
+            EditMode::Context if focused => f.render_widget(&self.context_input.textarea, area),
+            EditMode::Context => f.render_widget(self.context_input.unfocused(), area),
+            EditMode::Group if focused => f.render_widget(&self.group_input.textarea, area),
+            EditMode::Group => f.render_widget(self.group_input.unfocused(), area),
+            EditMode::Regex | EditMode::Body | EditMode::Cases => {}
         }
     }
 
+    fn draw_match(&self, f: &mut Frame) {
+        self.draw_frame(f, None);
+    }
+
     fn draw_substitution(&self, f: &mut Frame) {
+        let Some(area) = self.draw_frame(f, Some(EditMode::Substitution)) else {
+            return;
+        };
+        let output = match self.worker.results().map(|results| &results.output) {
+            Some(Output::Substituted(output)) => output.as_str(),
+            _ => "",
+        };
+        f.render_widget(substitution(output), area);
+    }
+
+    fn draw_split(&self, f: &mut Frame) {
+        let Some(area) = self.draw_frame(f, Some(EditMode::Limit)) else {
+            return;
+        };
+        let found = match self.worker.results().map(|results| &results.output) {
+            Some(Output::Fields(fields)) => fields.as_slice(),
+            _ => &[],
+        };
+        f.render_widget(fields(found, self.nul_separated), area);
+    }
+
+    fn draw_filter(&self, f: &mut Frame) {
+        let Some(area) = self.draw_frame(f, Some(EditMode::Context)) else {
+            return;
+        };
+        let selected = match self.worker.results().map(|results| &results.output) {
+            Some(Output::Selected(selected)) => selected.as_slice(),
+            _ => &[],
+        };
+        f.render_widget(
+            filtered(self.body.body(), selected, self.filter_options()),
+            area,
+        );
+    }
+
+    fn draw_extract(&self, f: &mut Frame) {
+        let Some(area) = self.draw_frame(f, Some(EditMode::Group)) else {
+            return;
+        };
+        f.render_widget(
+            extracted(
+                self.body.body(),
+                self.regex_input.current_regex(),
+                self.found(),
+                self.group_input.current_group(),
+            ),
+            area,
+        );
+    }
+
+    /// Draw the header, the pattern list, the input as `body` while the list is edited and the
+    /// footer of the modes taking a list of patterns. Returns the area for their output.
+    fn draw_patterns_frame(&self, f: &mut Frame, engine: Engine, body: impl Widget) -> Rect {
+        let flags = &self.regex_input.flags;
         let layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints(vec![
                 Constraint::Length(2),
-                Constraint::Length(3),
+                Constraint::Length(self.patterns_input.height()),
+                Constraint::Length(self.patterns_input.status_height()),
                 Constraint::Fill(1),
                 Constraint::Fill(1),
-                Constraint::Fill(1),
-                Constraint::Length(1),
+                Constraint::Length(2),
             ])
             .split(f.area());
-
-        let input_layout = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints(vec![Constraint::Fill(1), Constraint::Fill(1)])
-            .split(layout[1]);
-        f.render_widget(header(), layout[0]);
-        f.render_widget(footer(), layout[5]);
+        f.render_widget(header(engine, None, self.worker.running()), layout[0]);
+        f.render_widget(footer(self.message.as_deref(), None), layout[5]);
+        f.render_widget(self.patterns_input.status(flags), layout[2]);
 
         match self.edit_mode {
             EditMode::Body => {
-                f.render_widget(self.regex_input.unfocused(), input_layout[0]);
-                f.render_widget(self.sub_input.unfocused(), input_layout[1]);
-                f.render_widget(&self.body.textarea, layout[2]);
+                f.render_widget(self.patterns_input.unfocused(flags), layout[1]);
+                f.render_widget(&self.body.textarea, layout[3]);
             }
-            EditMode::Regex => {
-                f.render_widget(&self.regex_input.textarea, input_layout[0]);
-                f.render_widget(self.sub_input.unfocused(), input_layout[1]);
-                f.render_widget(
-                    self.body.highlighted_body(self.regex_input.current_regex()),
-                    layout[2],
-                );
-            }
-            EditMode::Substitution => {
-                f.render_widget(self.regex_input.unfocused(), input_layout[0]);
-                f.render_widget(&self.sub_input.textarea, input_layout[1]);
-                f.render_widget(
-                    self.body.highlighted_body(self.regex_input.current_regex()),
-                    layout[2],
-                );
+            _ => {
+                f.render_widget(&self.patterns_input.textarea, layout[1]);
+                f.render_widget(body, layout[3]);
             }
         }
+        layout[4]
+    }
+
+    fn draw_set(&self, f: &mut Frame) {
+        let (segments, line_hits) = match self.worker.results().map(|results| &results.output) {
+            Some(Output::Set { segments, hits }) => (segments.as_slice(), Some(hits.as_slice())),
+            _ => (&[][..], None),
+        };
+        // Sets are only supported by the regex crate.
+        let area =
+            self.draw_patterns_frame(f, Engine::Regex, self.body.highlighted_segments(segments));
         f.render_widget(
-            substitution(
+            hits(
                 self.body.body(),
-                self.regex_input.current_regex(),
-                self.sub_input.current_substitution(),
+                self.patterns_input.current_set(),
+                line_hits,
             ),
-            layout[3],
+            area,
         );
+    }
 
-        match self.info_mode {
-            InfoMode::QuickReference => f.render_widget(help(), layout[4]),
-            InfoMode::Captures => f.render_widget(
-                captures(self.regex_input.current_regex(), self.body.body()),
-                layout[4],
-            ),
-        }
+    fn draw_bench(&self, f: &mut Frame) {
+        let area = self.draw_patterns_frame(
+            f,
+            self.regex_input.engine,
+            self.body.highlighted_body(&[], self.highlight, None),
+        );
+        let measurements = match self.worker.results().map(|results| &results.output) {
+            Some(Output::Bench(measurements)) => Some(measurements.as_slice()),
+            _ => None,
+        };
+        f.render_widget(
+            timings(measurements, self.body.body().len(), self.runs),
+            area,
+        );
     }
 
     fn toggle_edit_mode(&mut self) {
         match (self.edit_mode, self.mode) {
-            (EditMode::Regex, Mode::Match) => self.edit_mode = EditMode::Body,
+            (EditMode::Regex, Mode::Match)
+            | (EditMode::Regex, Mode::Set)
+            | (EditMode::Regex, Mode::Bench) => self.edit_mode = EditMode::Body,
             (EditMode::Regex, Mode::Substitution) => self.edit_mode = EditMode::Substitution,
-            (EditMode::Substitution, _) => self.edit_mode = EditMode::Body,
-            (EditMode::Body, _) => self.edit_mode = EditMode::Regex,
+            (EditMode::Regex, Mode::Split) => self.edit_mode = EditMode::Limit,
+            (EditMode::Regex, Mode::Filter) => self.edit_mode = EditMode::Context,
+            (EditMode::Regex, Mode::Extract) => self.edit_mode = EditMode::Group,
+            (EditMode::Substitution, _)
+            | (EditMode::Limit, _)
+            | (EditMode::Context, _)
+            | (EditMode::Group, _) => self.edit_mode = EditMode::Body,
+            (EditMode::Body, Mode::Set | Mode::Bench) => self.edit_mode = EditMode::Regex,
+            (EditMode::Body, _) if matches!(self.info_mode, InfoMode::Tests) => {
+                self.edit_mode = EditMode::Cases
+            }
+            (EditMode::Body, _) | (EditMode::Cases, _) => self.edit_mode = EditMode::Regex,
         }
     }
     fn toggle_info_mode(&mut self) {
         match self.info_mode {
             InfoMode::QuickReference => self.info_mode = InfoMode::Captures,
-            InfoMode::Captures => self.info_mode = InfoMode::QuickReference,
+            InfoMode::Captures => self.info_mode = InfoMode::Match,
+            InfoMode::Match => self.info_mode = InfoMode::Explain,
+            InfoMode::Explain => self.info_mode = InfoMode::Tests,
+            InfoMode::Tests => {
+                self.info_mode = InfoMode::QuickReference;
+                if let EditMode::Cases = self.edit_mode {
+                    self.edit_mode = EditMode::Body;
+                }
+            }
+        }
+    }
+
+    fn toggle_highlight(&mut self) {
+        match self.highlight {
+            Highlight::Matches => self.highlight = Highlight::Groups,
+            Highlight::Groups => self.highlight = Highlight::Matches,
         }
     }
 
@@ -201,15 +1101,60 @@ impl App<'_> {
         match self.mode {
             Mode::Match => self.mode = Mode::Substitution,
             Mode::Substitution => {
-                self.mode = Mode::Match;
+                self.mode = Mode::Split;
                 if let EditMode::Substitution = self.edit_mode {
+                    self.edit_mode = EditMode::Limit;
+                }
+            }
+            Mode::Split => {
+                self.mode = Mode::Filter;
+                if let EditMode::Limit = self.edit_mode {
+                    self.edit_mode = EditMode::Context;
+                }
+            }
+            Mode::Filter => {
+                self.mode = Mode::Extract;
+                if let EditMode::Context = self.edit_mode {
+                    self.edit_mode = EditMode::Group;
+                }
+            }
+            Mode::Extract => {
+                self.mode = Mode::Set;
+                // Set mode has no info pane to edit the test cases in.
+                if let EditMode::Group | EditMode::Cases = self.edit_mode {
                     self.edit_mode = EditMode::Regex;
                 }
             }
+            Mode::Set => {
+                self.mode = Mode::Bench;
+                self.lead_with_regex();
+            }
+            Mode::Bench => self.mode = Mode::Match,
         }
+        // Bench mode runs the patterns on the engine of the regex, set mode on the regex crate.
+        self.validate_patterns();
     }
     fn handle_input(&mut self) -> io::Result<Action> {
-        match (crossterm::event::read()?.into(), self.edit_mode) {
+        let event = crossterm::event::read()?;
+        if let crossterm::event::Event::Key(_) = event {
+            self.message = None;
+        }
+        if self.search.is_some() {
+            self.handle_search(event.into());
+            return Ok(Action::Continue);
+        }
+        if self.browser.is_some() {
+            self.handle_browser(event.into());
+            return Ok(Action::Continue);
+        }
+        if self.export.is_some() {
+            return Ok(self.handle_export(event.into()));
+        }
+        if self.copy_menu.is_some() {
+            self.handle_copy(event.into());
+            return Ok(Action::Continue);
+        }
+        match (event.into(), self.edit_mode) {
             (
                 Input {
                     key: Key::Char('q'),
@@ -226,7 +1171,10 @@ impl App<'_> {
                     ..
                 },
                 _,
-            ) => return Ok(Action::ReturnValue(self.regex_input.current_regex_str())),
+            ) => {
+                self.remember();
+                return Ok(Action::ReturnValue(self.exported_regex()));
+            }
             (
                 Input {
                     key: Key::Char('o'),
@@ -234,19 +1182,73 @@ impl App<'_> {
                     ..
                 },
                 _,
-            ) => match self.regex_input.current_regex() {
-                None => return Ok(Action::ReturnValue(self.body.body())),
-                Some(reg) => {
-                    return Ok(Action::ReturnValue(
-                        reg.replace_all(
-                            &self.body.body(),
-                            self.sub_input.current_substitution().to_string(),
-                        )
-                        .to_string(),
-                    ))
+            ) => match self.output() {
+                Ok(output) => {
+                    self.remember();
+                    return Ok(Action::ReturnValue(output));
                 }
+                Err(err) => self.message = Some(err),
             },
             (Input { key: Key::Tab, .. }, _) => self.toggle_edit_mode(),
+            (
+                Input {
+                    key: Key::Char('w'),
+                    alt: true,
+                    ..
+                },
+                _,
+            ) => self.save_session(),
+            (
+                Input {
+                    key: Key::Char('l'),
+                    alt: true,
+                    ..
+                },
+                _,
+            ) => self.browser = Some(library_browser()),
+            (
+                Input {
+                    key: Key::Char('e'),
+                    alt: true,
+                    ..
+                },
+                _,
+            ) => self.open_export(),
+            (
+                Input {
+                    key: Key::Char('c'),
+                    alt: true,
+                    ..
+                },
+                _,
+            ) => self.copy_menu = Some(CopyMenu::default()),
+            (
+                Input {
+                    key: Key::Char('n'),
+                    alt: true,
+                    ..
+                },
+                _,
+            ) => self.step_match(true),
+            (
+                Input {
+                    key: Key::Char('p'),
+                    alt: true,
+                    ..
+                },
+                _,
+            ) => self.step_match(false),
+            (
+                Input {
+                    key: Key::Char(c),
+                    alt: true,
+                    ..
+                },
+                _,
+            ) if FLAGS.iter().any(|(flag, _)| *flag == c) => {
+                self.regex_input.toggle_flag(c);
+                self.validate_patterns();
+            }
             (
                 Input {
                     key: Key::Char('h'),
@@ -263,17 +1265,138 @@ impl App<'_> {
                 },
                 _,
             ) => self.toggle_mode(),
+            (
+                Input {
+                    key: Key::Char('g'),
+                    ctrl: true,
+                    ..
+                },
+                _,
+            ) => self.toggle_highlight(),
+            (
+                Input {
+                    key: Key::Char('n'),
+                    ctrl: true,
+                    ..
+                },
+                _,
+            ) => {
+                self.regex_input.toggle_engine();
+                self.validate_patterns();
+            }
+            (
+                Input {
+                    key: Key::Char('t'),
+                    ctrl: true,
+                    ..
+                },
+                _,
+            ) => self.nul_separated = !self.nul_separated,
+            (
+                Input {
+                    key: Key::Char('v'),
+                    ctrl: true,
+                    ..
+                },
+                _,
+            ) => self.invert = !self.invert,
+            (
+                Input {
+                    key: Key::Char('l'),
+                    ctrl: true,
+                    ..
+                },
+                _,
+            ) => self.line_numbers = !self.line_numbers,
+            (
+                Input {
+                    key: Key::Char('s'),
+                    ctrl: true,
+                    ..
+                },
+                _,
+            ) => self.save_tests(),
+            // The highlighted input scrolls on its own while another field has focus.
+            (
+                Input {
+                    key: Key::MouseScrollDown,
+                    ..
+                },
+                mode,
+            ) if !matches!(mode, EditMode::Body) => self.body.scroll(3),
+            (
+                Input {
+                    key: Key::MouseScrollUp,
+                    ..
+                },
+                mode,
+            ) if !matches!(mode, EditMode::Body) => self.body.scroll(-3),
+            (
+                Input {
+                    key: Key::PageDown, ..
+                },
+                mode,
+            ) if !matches!(mode, EditMode::Body | EditMode::Cases)
+                && !matches!(self.mode, Mode::Set | Mode::Bench) =>
+            {
+                self.body.scroll_page(true)
+            }
+            (
+                Input {
+                    key: Key::PageUp, ..
+                },
+                mode,
+            ) if !matches!(mode, EditMode::Body | EditMode::Cases)
+                && !matches!(self.mode, Mode::Set | Mode::Bench) =>
+            {
+                self.body.scroll_page(false)
+            }
             (input, EditMode::Body) => {
-                self.body.textarea.input(input);
+                self.body.input(input);
             }
+            (input, EditMode::Regex) if matches!(self.mode, Mode::Set | Mode::Bench) => {
+                if self.patterns_input.textarea.input(input) {
+                    self.patterns_edited = true;
+                    self.validate_patterns()
+                }
+            }
+            (Input { key: Key::Up, .. }, EditMode::Regex) => self.history_back(),
+            (Input { key: Key::Down, .. }, EditMode::Regex) => self.history_forward(),
+            (
+                Input {
+                    key: Key::Char('r'),
+                    ctrl: true,
+                    ..
+                },
+                EditMode::Regex,
+            ) => self.search = Some(history_search()),
             (input, EditMode::Regex) => {
                 if self.regex_input.textarea.input(input) {
+                    self.recalled = None;
                     self.regex_input.validate()
                 }
             }
             (input, EditMode::Substitution) => {
                 self.sub_input.textarea.input(input);
             }
+            (input, EditMode::Limit) => {
+                if self.limit_input.textarea.input(input) {
+                    self.limit_input.validate()
+                }
+            }
+            (input, EditMode::Cases) => {
+                if self.cases_input.textarea.input(input) {
+                    self.cases_input.saved = None;
+                }
+            }
+            (input, EditMode::Group) => {
+                self.group_input.textarea.input(input);
+            }
+            (input, EditMode::Context) => {
+                if self.context_input.textarea.input(input) {
+                    self.context_input.validate()
+                }
+            }
         }
         Ok(Action::Continue)
     }
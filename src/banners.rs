@@ -1,12 +1,32 @@
+use std::time::Duration;
+
 use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
     style::{Color, Style},
+    text::{Line, Span},
     widgets::{Block, BorderType, Borders, Paragraph, Widget},
 };
+
+use crate::engine::{Engine, Program};
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
 const HEADER: &str = r"┏┓┏┓┏┓┏┓┏╋
 ┛ ┗ ┗┫┗ ┗┗
 ";
-const FOOTER: &str =
-    r"^x toggle match/substitution, ^e export regex, ^o export output, ^h quick reference, ^q quit";
+const FOOTER: &str = r"^x mode, ^e/^o export regex/output, ^h info and keys, ^q quit";
+
+const KEYS: &str = r"
+Keys
+Tab            next field           ^x             next mode            ^e             print regex on exit
+Up/Down        history (regex)      ^h             next info pane       ^o             print output on exit
+^r             search history       ^g             highlight groups     alt+e          export as code
+alt+l          pattern library      ^n             switch engine        alt+c          copy
+alt+n/p        next/prev match      ^t             NUL split (split)    ^s             save tests
+PgUp/PgDn      scroll input         ^v             invert (filter)      alt+w          save session
+                                    ^l             numbers (filter)     ^q             quit
+";
 
 const HELP: &str = r"
 Match                               Quantifiers                         Groups & Substitution
@@ -27,17 +47,163 @@ Match                               Quantifiers                         Groups &
 $              the end of a haystack
 ";
 
-pub fn header() -> impl Widget {
-    Paragraph::new(HEADER)
-        .centered()
-        .style(Style::default().fg(Color::Cyan))
+const HELP_FANCY: &str = r"
+Lookaround & Backreferences (fancy-regex)
+(?=exp)        lookahead            (?<=exp)       lookbehind           \1             backreference to group 1
+(?!exp)        negative lookahead   (?<!exp)       negative lookbehind  \k<name>       backreference to group name
+(?>exp)        atomic group         x*+            possessive x*        \G             end of the previous match
+";
+
+/// The banner with the engine and, when there are test cases, how many of them passed. A
+/// spinner with the time taken so far is shown while matching.
+pub fn header(
+    engine: Engine,
+    tests: Option<(usize, usize)>,
+    matching: Option<Duration>,
+) -> impl Widget {
+    struct Header(Engine, Option<(usize, usize)>, Option<Duration>);
+
+    impl Widget for Header {
+        fn render(self, area: Rect, buf: &mut Buffer) {
+            Paragraph::new(HEADER)
+                .centered()
+                .style(Style::default().fg(Color::Cyan))
+                .render(area, buf);
+            if let Some((passed, total)) = self.1 {
+                Paragraph::new(format!("tests: {}/{} passed", passed, total))
+                    .style(Style::default().fg(if passed == total {
+                        Color::Green
+                    } else {
+                        Color::LightRed
+                    }))
+                    .render(area, buf);
+            }
+            let mut spans = vec![];
+            if let Some(elapsed) = self.2 {
+                let frame = SPINNER[(elapsed.as_millis() / 100) as usize % SPINNER.len()];
+                spans.push(Span::styled(
+                    format!("{} matching {:.1}s  ", frame, elapsed.as_secs_f64()),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+            spans.push(Span::styled(
+                format!("engine: {}", self.0.name()),
+                Style::default().fg(Color::Gray),
+            ));
+            Paragraph::new(Line::from(spans))
+                .right_aligned()
+                .render(area, buf);
+        }
+    }
+
+    Header(engine, tests, matching)
 }
-pub fn footer() -> impl Widget {
-    Paragraph::new(FOOTER).right_aligned()
+/// Searches taking this long have their time highlighted in the status line.
+const SLOW: Duration = Duration::from_millis(100);
+
+/// Live diagnostics of the regex, to spot slow patterns.
+pub struct Status<'a> {
+    /// Left out while still searching.
+    pub matches: usize,
+    /// Groups that took part in the matches, not counting the whole match.
+    pub captures: usize,
+    /// The search timed out, there are more matches than counted.
+    pub partial: bool,
+    pub compile_time: Duration,
+    /// None while still searching.
+    pub search_time: Option<Duration>,
+    /// None with the fancy-regex engine.
+    pub program: Option<&'a Program>,
+}
+
+impl Status<'_> {
+    fn line(&self) -> Line<'static> {
+        let gray = Style::default().fg(Color::Gray);
+        // The matches are only counted once the search is done.
+        let counts = match self.search_time {
+            Some(_) => format!(
+                "{}{} match{}, {} capture{}, ",
+                self.matches,
+                if self.partial { "+" } else { "" },
+                if self.matches == 1 { "" } else { "es" },
+                self.captures,
+                if self.captures == 1 { "" } else { "s" },
+            ),
+            None => String::new(),
+        };
+        let mut spans = vec![Span::styled(
+            format!("{}compiled in {}, ", counts, duration(self.compile_time)),
+            gray,
+        )];
+        spans.push(match self.search_time {
+            Some(time) if time >= SLOW => Span::styled(
+                format!("searched in {}", duration(time)),
+                Style::default().fg(Color::Yellow),
+            ),
+            Some(time) => Span::styled(format!("searched in {}", duration(time)), gray),
+            None => Span::styled("searching", gray),
+        });
+        let program = match self.program {
+            Some(program) => {
+                let prefixes = program
+                    .prefixes
+                    .iter()
+                    .take(3)
+                    .map(|p| format!("{:?}", p))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let prefixes = match program.prefixes.len() {
+                    0 => "no literal prefix".to_string(),
+                    1 => format!("literal prefix {}", prefixes),
+                    n if n <= 3 => format!("literal prefixes {}", prefixes),
+                    n => format!("{} literal prefixes {} …", n, prefixes),
+                };
+                format!(", program {}, {}", bytes(program.size), prefixes)
+            }
+            None => ", runs on the fancy-regex backtracking VM".to_string(),
+        };
+        spans.push(Span::styled(program, gray));
+        Line::from(spans)
+    }
+}
+
+/// A duration in the unit that keeps it short, e.g. `850µs` or `1.2ms`.
+pub fn duration(duration: Duration) -> String {
+    match duration.as_micros() {
+        us if us < 1000 => format!("{}µs", us),
+        us if us < 1_000_000 => format!("{:.1}ms", us as f64 / 1000.0),
+        _ => format!("{:.2}s", duration.as_secs_f64()),
+    }
+}
+
+/// A size in bytes in the unit that keeps it short, e.g. `512 B` or `3.4 KiB`.
+pub fn bytes(bytes: usize) -> String {
+    match bytes {
+        b if b < 1024 => format!("{} B", b),
+        b if b < 1024 * 1024 => format!("{:.1} KiB", b as f64 / 1024.0),
+        b => format!("{:.1} MiB", b as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+/// The status of the regex, when there is a valid one, over the key bindings or a message
+/// about the last action in their place.
+pub fn footer(message: Option<&str>, status: Option<Status>) -> impl Widget {
+    let keys = match message {
+        Some(message) => {
+            Line::styled(message.to_string(), Style::default().fg(Color::Yellow)).right_aligned()
+        }
+        None => Line::raw(FOOTER).right_aligned(),
+    };
+    let status = status.map_or(Line::default(), |status| status.line());
+    Paragraph::new(vec![status, keys])
 }
 
-pub fn help() -> impl Widget {
-    Paragraph::new(HELP).block(
+pub fn help(engine: Engine) -> impl Widget {
+    let help = match engine {
+        Engine::Regex => format!("{}{}", KEYS, HELP),
+        Engine::Fancy => format!("{}{}{}", KEYS, HELP, HELP_FANCY),
+    };
+    Paragraph::new(help).block(
         Block::new()
             .border_type(BorderType::Rounded)
             .border_style(Style::default())
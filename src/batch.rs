@@ -0,0 +1,171 @@
+use std::process::ExitCode;
+
+use crate::{
+    app::Mode,
+    bench::{bench, report, variants, DEFAULT_RUNS},
+    body::{extract, split, substitute},
+    cli::Args,
+    engine::Engine,
+    filter::{filter, filter_lines, FilteredLine},
+    set::{classify, PatternSet},
+    suite::Suite,
+};
+
+/// Apply the regex to the input without starting the TUI. In substitution mode the
+/// substituted input is printed, in match mode every match and in split mode every field
+/// on its own line. Filter mode prints the selected lines like grep and extract mode the
+/// chosen group of every match. Set mode prints every line behind the patterns hitting it
+/// and bench mode the timings of every pattern.
+pub fn run(args: &Args, patterns: Vec<String>, input: Vec<String>) -> ExitCode {
+    match args.mode {
+        Mode::Set => return run_set(args, patterns, input),
+        Mode::Bench => return run_bench(args, patterns, input),
+        _ => (),
+    }
+    let regex = args.regex.as_deref().unwrap_or_default();
+    let reg = match args.engine.build(regex, &args.flags) {
+        Ok(reg) => reg,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::from(2);
+        }
+    };
+    let body = input.join("\n");
+
+    match args.mode {
+        Mode::Set => unreachable!("set mode is handled by run_set"),
+        Mode::Bench => unreachable!("bench mode is handled by run_bench"),
+        Mode::Filter => {
+            let options = args.filter();
+            let selected = filter_lines(&body, Some(&reg), options)
+                .iter()
+                .any(|line| matches!(line, FilteredLine::Line { selected: true, .. }));
+            if !selected {
+                return ExitCode::FAILURE;
+            }
+            println!("{}", filter(&body, Some(&reg), options));
+        }
+        Mode::Extract => {
+            let group = args.group.as_deref().unwrap_or_default();
+            match extract(
+                &body,
+                Some(&reg),
+                &reg.captures_iter(&body).collect::<Vec<_>>(),
+                group,
+            ) {
+                Ok(matches) if matches.is_empty() => return ExitCode::FAILURE,
+                Ok(matches) => {
+                    for m in matches {
+                        println!("{}", m);
+                    }
+                }
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return ExitCode::from(2);
+                }
+            }
+        }
+        Mode::Substitution | Mode::Split if !reg.is_match(&body) => {
+            println!("{}", body);
+            return ExitCode::FAILURE;
+        }
+        _ if !reg.is_match(&body) => return ExitCode::FAILURE,
+        Mode::Match => {
+            for range in reg.find_iter(&body) {
+                println!("{}", &body[range]);
+            }
+        }
+        Mode::Substitution => {
+            let substitution = args.sub.clone().unwrap_or_default();
+            match substitute(&body, Some(&reg), substitution) {
+                Ok(output) => println!("{}", output),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return ExitCode::from(2);
+                }
+            }
+        }
+        Mode::Split => {
+            for field in split(&body, Some(&reg), args.limit) {
+                println!("{}", field);
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn run_set(args: &Args, patterns: Vec<String>, input: Vec<String>) -> ExitCode {
+    let patterns = match patterns.is_empty() {
+        true => vec![args.regex.clone().unwrap_or_default()],
+        false => patterns,
+    };
+    let mut valid = true;
+    for (i, pattern) in patterns.iter().enumerate() {
+        if let Err(err) = Engine::Regex.build(pattern, &args.flags) {
+            eprintln!("pattern {}: {}", i + 1, err);
+            valid = false;
+        }
+    }
+    let set = match PatternSet::new(&patterns, &args.flags) {
+        Some(set) if valid => set,
+        _ => return ExitCode::from(2),
+    };
+
+    let body = input.join("\n");
+    println!("{}", classify(&body, Some(&set)));
+    if body.lines().all(|line| set.hits(line).is_empty()) {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Time every pattern, exits with 1 when the matches of any pattern differ from those of
+/// the first.
+fn run_bench(args: &Args, patterns: Vec<String>, input: Vec<String>) -> ExitCode {
+    let patterns = match patterns.is_empty() {
+        true => vec![args.regex.clone().unwrap_or_default()],
+        false => patterns,
+    };
+    let mut valid = true;
+    for (i, pattern) in patterns.iter().enumerate() {
+        if let Err(err) = args.engine.build(pattern, &args.flags) {
+            eprintln!("pattern {}: {}", i + 1, err);
+            valid = false;
+        }
+    }
+    if !valid {
+        return ExitCode::from(2);
+    }
+
+    let body = input.join("\n");
+    let variants = variants(&patterns, &args.flags, args.engine);
+    let measurements = bench(&variants, &body, args.runs.unwrap_or(DEFAULT_RUNS), || {
+        false
+    });
+    println!("{}", report(&measurements, body.len()));
+    if measurements.iter().any(|m| m.differs) {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Run the cases of a test suite, exits with 1 when any of them fails.
+pub fn run_suite(suite: &Suite) -> ExitCode {
+    if let Err(err) = suite.engine.build(&suite.regex, &suite.flags) {
+        eprintln!("{}", err);
+        return ExitCode::from(2);
+    }
+    let outcomes = suite.run();
+    for (case, outcome) in suite.cases.iter().zip(&outcomes) {
+        match outcome {
+            Ok(()) => println!("ok    {}", case),
+            Err(err) => println!("FAIL  {}: {}", case, err),
+        }
+    }
+    let failed = outcomes.iter().filter(|o| o.is_err()).count();
+    println!("{} passed, {} failed", outcomes.len() - failed, failed);
+    match failed {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    }
+}
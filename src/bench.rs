@@ -0,0 +1,227 @@
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    layout::Constraint,
+    style::{Color, Style},
+    widgets::{Block, BorderType, Borders, Cell, Row, Table, Widget},
+};
+
+use crate::{
+    banners::{bytes, duration},
+    body::get_color,
+    engine::{Engine, Regex},
+    flags::Flags,
+};
+
+/// Timed runs of every pattern when `--runs` is not given.
+pub const DEFAULT_RUNS: usize = 10;
+
+/// A pattern of the list, built with the engine.
+pub struct Variant {
+    /// Line of the pattern in the list, empty and invalid lines do not shift it.
+    pub position: usize,
+    pub pattern: String,
+    pub regex: Regex,
+}
+
+/// The valid patterns of the list, the first one is the baseline the others are compared to.
+pub fn variants(patterns: &[String], flags: &Flags, engine: Engine) -> Vec<Variant> {
+    patterns
+        .iter()
+        .enumerate()
+        .filter(|(_, pattern)| !pattern.is_empty())
+        .filter_map(|(position, pattern)| {
+            let regex = engine.build(pattern, flags).ok()?;
+            Some(Variant {
+                position,
+                pattern: pattern.clone(),
+                regex,
+            })
+        })
+        .collect()
+}
+
+pub struct Measurement {
+    pub position: usize,
+    pub pattern: String,
+    /// Time of every run, fewer than asked for when stopped early.
+    pub times: Vec<Duration>,
+    pub matches: usize,
+    /// The matches are not those of the baseline.
+    pub differs: bool,
+}
+
+impl Measurement {
+    pub fn mean(&self) -> Duration {
+        self.times.iter().sum::<Duration>() / self.times.len().max(1) as u32
+    }
+
+    pub fn median(&self) -> Duration {
+        let mut times = self.times.clone();
+        times.sort_unstable();
+        match times.len() {
+            0 => Duration::ZERO,
+            n if n % 2 == 0 => (times[n / 2 - 1] + times[n / 2]) / 2,
+            n => times[n / 2],
+        }
+    }
+
+    /// Megabytes of input searched per second, by the mean time.
+    pub fn throughput(&self, size: usize) -> f64 {
+        size as f64 / self.mean().as_secs_f64().max(f64::MIN_POSITIVE) / 1e6
+    }
+}
+
+/// Search the body for all matches of every variant `runs` times. An untimed round first warms
+/// up the lazy DFA and collects the matches to compare with the baseline. The variants take
+/// turns so noise, like another process waking up, hits all of them alike. After the first
+/// timed round `stop` is asked before every run.
+pub fn bench(
+    variants: &[Variant],
+    body: &str,
+    runs: usize,
+    stop: impl Fn() -> bool,
+) -> Vec<Measurement> {
+    let found = variants
+        .iter()
+        .map(|variant| variant.regex.find_iter(body).collect::<Vec<Range<usize>>>())
+        .collect::<Vec<_>>();
+    let mut times = vec![vec![]; variants.len()];
+    'rounds: for round in 0..runs.max(1) {
+        for (variant, times) in variants.iter().zip(times.iter_mut()) {
+            if round > 0 && stop() {
+                break 'rounds;
+            }
+            let started = Instant::now();
+            variant.regex.find_iter(body).count();
+            times.push(started.elapsed());
+        }
+    }
+    variants
+        .iter()
+        .zip(times)
+        .zip(&found)
+        .map(|((variant, times), matches)| Measurement {
+            position: variant.position,
+            pattern: variant.pattern.clone(),
+            times,
+            matches: matches.len(),
+            differs: matches != &found[0],
+        })
+        .collect()
+}
+
+/// How a measurement compares to the baseline, empty for the baseline itself.
+fn note(measurement: &Measurement, baseline: &Measurement) -> String {
+    if measurement.position == baseline.position {
+        return String::new();
+    }
+    if measurement.differs {
+        return format!("matches differ from pattern {}", baseline.position + 1);
+    }
+    let ratio =
+        baseline.mean().as_secs_f64() / measurement.mean().as_secs_f64().max(f64::MIN_POSITIVE);
+    format!(
+        "{:.2}x the speed of pattern {}",
+        ratio,
+        baseline.position + 1
+    )
+}
+
+/// The measurements as a plain table, for batch mode.
+pub fn report(measurements: &[Measurement], size: usize) -> String {
+    let width = measurements
+        .iter()
+        .map(|m| m.pattern.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("pattern".len());
+    let mut lines = vec![format!(
+        "{:>3}  {:<width$}  {:>10}  {:>9}  {:>9}  {:>4}  {:>8}",
+        "#",
+        "pattern",
+        "MB/s",
+        "mean",
+        "median",
+        "runs",
+        "matches",
+        width = width
+    )];
+    for m in measurements {
+        let line = format!(
+            "{:>3}  {:<width$}  {:>10.1}  {:>9}  {:>9}  {:>4}  {:>8}  {}",
+            m.position + 1,
+            m.pattern,
+            m.throughput(size),
+            duration(m.mean()),
+            duration(m.median()),
+            m.times.len(),
+            m.matches,
+            note(m, &measurements[0]),
+            width = width
+        );
+        lines.push(line.trim_end().to_string());
+    }
+    lines.join("\n")
+}
+
+/// The measurements of the patterns, none while they run.
+pub fn timings(measurements: Option<&[Measurement]>, size: usize, runs: usize) -> impl Widget {
+    let header = Row::new(vec![
+        "#", "pattern", "MB/s", "mean", "median", "runs", "matches", "",
+    ])
+    .style(Style::default().fg(Color::Gray))
+    .bottom_margin(1);
+    let measurements = measurements.unwrap_or_default();
+    let rows = measurements
+        .iter()
+        .map(|m| {
+            let note = note(m, &measurements[0]);
+            Row::new(vec![
+                Cell::new((m.position + 1).to_string())
+                    .style(Style::default().fg(get_color(m.position))),
+                Cell::new(m.pattern.clone()),
+                Cell::new(format!("{:.1}", m.throughput(size))),
+                Cell::new(duration(m.mean())),
+                Cell::new(duration(m.median())),
+                Cell::new(m.times.len().to_string()),
+                Cell::new(m.matches.to_string()),
+                Cell::new(note).style(match m.differs {
+                    true => Style::default().fg(Color::LightRed),
+                    false => Style::default().fg(Color::Gray),
+                }),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    Table::new(
+        rows,
+        vec![
+            Constraint::Length(3),
+            Constraint::Fill(1),
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(4),
+            Constraint::Length(8),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(header)
+    .column_spacing(2)
+    .block(
+        Block::new()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Gray))
+            .borders(Borders::ALL)
+            .title(format!(
+                "Bench, {} run{} over {}",
+                runs,
+                if runs == 1 { "" } else { "s" },
+                bytes(size)
+            )),
+    )
+}
@@ -1,11 +1,17 @@
+use std::{cell, ops::Range, sync::Arc};
+
 use ratatui::{
-    layout::Constraint,
-    style::{Color, Style},
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, BorderType, Borders, Paragraph, Row, Table, Widget},
+    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, Widget},
 };
-use regex::Regex;
-use ratatui_textarea::TextArea;
+use ratatui_textarea::{CursorMove, Input, TextArea};
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{self, Captures, Regex};
 
 pub fn get_color(index: usize) -> Color {
     match index % 5 {
@@ -16,13 +22,31 @@ pub fn get_color(index: usize) -> Color {
         _ => Color::Magenta,
     }
 }
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Highlight {
+    #[default]
+    Matches,
+    Groups,
+}
+
 pub struct TestInput<'a> {
     pub textarea: TextArea<'a>,
+    /// The lines of the textarea joined, kept up to date by `input`. Shared with the worker
+    /// matching it.
+    text: Arc<str>,
+    /// Counts the edits, tells the results of an earlier text apart.
+    version: u64,
+    /// First line and height of the highlighted view when it was last drawn.
+    top: cell::Cell<usize>,
+    height: cell::Cell<usize>,
 }
 
 impl TestInput<'_> {
-    pub fn new() -> Self {
-        let mut textarea = TextArea::default();
+    pub fn new(body: String) -> Self {
+        let mut textarea = TextArea::new(body.split('\n').map(String::from).collect());
+        textarea.move_cursor(CursorMove::Bottom);
+        textarea.move_cursor(CursorMove::End);
         textarea.set_style(Style::default().fg(Color::LightGreen));
         textarea.set_block(
             Block::default()
@@ -30,89 +54,432 @@ impl TestInput<'_> {
                 .borders(Borders::ALL)
                 .title("Input"),
         );
-        Self { textarea }
+        Self {
+            textarea,
+            text: body.into(),
+            version: 0,
+            top: cell::Cell::new(0),
+            height: cell::Cell::new(0),
+        }
     }
 
-    pub fn body(&self) -> String {
-        self.textarea.lines().join("\n").to_string()
+    pub fn body(&self) -> &str {
+        &self.text
     }
-    pub fn highlighted_body(&self, current_regex: Option<Regex>) -> impl Widget + '_ {
-        fn append_match(part: &str, lines: &mut Vec<Vec<Span>>, style: Style) {
-            let mut last = lines.len() - 1;
-            if !part.contains('\n') {
-                lines[last].push(Span::styled(part.to_owned(), style));
-                return;
-            }
 
-            for p in part.split('\n') {
-                lines[last].push(Span::styled(p.to_owned(), style));
-                last += 1;
-                lines.push(vec![]);
-            }
+    pub fn shared(&self) -> Arc<str> {
+        self.text.clone()
+    }
 
-            lines.pop();
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Pass the input on to the textarea, whether it changed the text.
+    pub fn input(&mut self, input: Input) -> bool {
+        let modified = self.textarea.input(input);
+        if modified {
+            self.text = self.textarea.lines().join("\n").into();
+            self.version += 1;
         }
-        let body = self.textarea.lines().join("\n");
-        let mut text = Text::default();
-        if let Some(regex) = current_regex {
-            let mut lines: Vec<Vec<Span>> = vec![vec![]];
-
-            let mut current_index = 0;
-
-            for (i, re_match) in regex.find_iter(&body).enumerate() {
-                append_match(
-                    &body[current_index..re_match.start()],
-                    &mut lines,
-                    Style::default(),
-                );
-                append_match(
-                    &body[re_match.start()..re_match.end()],
-                    &mut lines,
-                    Style::default().fg(Color::Black).bg(get_color(i)),
-                );
-                current_index = re_match.end();
-            }
-            append_match(&body[current_index..], &mut lines, Style::default());
-            for line in lines {
-                text.push_line(Line::from(line));
-            }
-        } else {
-            text = body.into();
-        };
+        modified
+    }
 
-        Paragraph::new(text).block(
-            Block::new()
+    /// Byte offset of the cursor in the body.
+    pub fn cursor_offset(&self) -> usize {
+        let (row, col) = self.textarea.cursor();
+        let lines = self.textarea.lines();
+        let line = &lines[row];
+        lines[..row].iter().map(|l| l.len() + 1).sum::<usize>()
+            + line.char_indices().nth(col).map_or(line.len(), |(i, _)| i)
+    }
+
+    /// Move the cursor to a byte offset in the body.
+    pub fn jump_to(&mut self, offset: usize) {
+        let (line, column) = position(&self.text, offset);
+        self.move_cursor(line - 1, column - 1);
+    }
+
+    pub fn set_title(&mut self, title: String) {
+        self.textarea.set_block(
+            Block::default()
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(Color::Gray))
                 .borders(Borders::ALL)
-                .title("Input"),
-        )
+                .title(title),
+        );
+    }
+
+    /// The body with the matches found in it highlighted. The selected match, an index in
+    /// the matches, is shown reversed. Only the lines in view are highlighted, the view
+    /// follows the cursor like the textarea does so both show the same lines.
+    pub fn highlighted_body<'a>(
+        &'a self,
+        found: &'a [Captures],
+        highlight: Highlight,
+        selected: Option<usize>,
+    ) -> impl Widget + 'a {
+        HighlightedBody {
+            input: self,
+            found,
+            highlight,
+            selected,
+            segments: None,
+        }
+    }
+
+    /// The body with styled segments, sorted and apart, in place of the matches.
+    pub fn highlighted_segments<'a>(
+        &'a self,
+        segments: &'a [(usize, usize, Style)],
+    ) -> impl Widget + 'a {
+        HighlightedBody {
+            input: self,
+            found: &[],
+            highlight: Highlight::Matches,
+            selected: None,
+            segments: Some(segments),
+        }
+    }
+
+    /// Scroll the highlighted view, the cursor moves along when it would leave the view.
+    pub fn scroll(&mut self, rows: isize) {
+        let last = self.textarea.lines().len().saturating_sub(1);
+        let top = self.top.get().saturating_add_signed(rows).min(last);
+        self.top.set(top);
+        let (row, col) = self.textarea.cursor();
+        let bottom = top + self.height.get().max(1) - 1;
+        if row < top {
+            self.move_cursor(top, col);
+        } else if row > bottom {
+            self.move_cursor(bottom.min(last), col);
+        }
+    }
+
+    /// Scroll the highlighted view by a page.
+    pub fn scroll_page(&mut self, down: bool) {
+        let page = self.height.get().max(1) as isize;
+        self.scroll(if down { page } else { -page });
+    }
+
+    /// Move the cursor to a row and column, rows past what `CursorMove::Jump` can reach
+    /// are walked to.
+    fn move_cursor(&mut self, row: usize, col: usize) {
+        let jump = row.min(u16::MAX as usize);
+        self.textarea.move_cursor(CursorMove::Jump(
+            jump as u16,
+            col.min(u16::MAX as usize) as u16,
+        ));
+        for _ in jump..row {
+            self.textarea.move_cursor(CursorMove::Down);
+        }
     }
 }
 
-pub fn captures(reg: Option<Regex>, body: String) -> impl Widget {
-    if let Some(reg) = reg {
-        let mut rows: Vec<Row> = vec![];
-        let names = reg
-            .capture_names()
+struct HighlightedBody<'a> {
+    input: &'a TestInput<'a>,
+    found: &'a [Captures],
+    highlight: Highlight,
+    selected: Option<usize>,
+    segments: Option<&'a [(usize, usize, Style)]>,
+}
+
+impl Widget for HighlightedBody<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let HighlightedBody {
+            input,
+            found,
+            highlight,
+            selected,
+            segments,
+        } = self;
+        let block = Block::new()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Gray))
+            .borders(Borders::ALL);
+        let lines = input.textarea.lines();
+        let height = area.height.saturating_sub(2) as usize;
+        input.height.set(height);
+        // On a terminal this small there is no line to show.
+        if height == 0 {
+            block.title(input_title(highlight, None)).render(area, buf);
+            return;
+        }
+        let top = next_top(input.top.get(), input.textarea.cursor().0, height)
+            .min(lines.len().saturating_sub(1));
+        let bottom = (top + height).min(lines.len());
+        input.top.set(top);
+
+        // Byte range of the lines in view, only the matches overlapping it are highlighted.
+        let body = input.body();
+        let start = lines[..top]
+            .iter()
+            .map(|l| l.len() + 1)
+            .sum::<usize>()
+            .min(body.len());
+        let end = (start
+            + lines[top..bottom]
+                .iter()
+                .map(|l| l.len() + 1)
+                .sum::<usize>()
+                .saturating_sub(1))
+        .min(body.len());
+        let window = start..end;
+
+        let selected = selected
+            .filter(|i| *i < found.len())
+            .and_then(|i| found[i][0].clone().map(|range| (i, range)));
+        let mut segments = match (segments, highlight) {
+            (Some(segments), _) => {
+                let first = segments.partition_point(|(_, end, _)| *end < window.start);
+                segments[first..]
+                    .iter()
+                    .take_while(|(start, _, _)| *start <= window.end)
+                    .copied()
+                    .collect()
+            }
+            (None, Highlight::Matches) => match_segments(found, &window),
+            (None, Highlight::Groups) => group_segments(found, &window),
+        };
+        if let Some((_, range)) = &selected {
+            for (start, end, style) in segments.iter_mut() {
+                if *start >= range.start && *end <= range.end {
+                    *style = style.add_modifier(Modifier::REVERSED);
+                }
+            }
+        }
+        let segments = segments
+            .into_iter()
+            .map(|(s, e, style)| {
+                let s = s.clamp(window.start, window.end);
+                (
+                    s - window.start,
+                    e.clamp(s, window.end) - window.start,
+                    style,
+                )
+            })
+            .collect();
+        let text = segmented(&body[window], segments);
+        let selected = selected.map(|(i, _)| (i, found.len()));
+
+        Paragraph::new(text)
+            .block(block.title(input_title(highlight, selected)))
+            .render(area, buf);
+    }
+}
+
+/// The first line in view, moved as little as possible to show the cursor. This is the rule
+/// the textarea scrolls by.
+fn next_top(top: usize, cursor: usize, height: usize) -> usize {
+    if cursor < top {
+        cursor
+    } else if top + height <= cursor {
+        cursor + 1 - height
+    } else {
+        top
+    }
+}
+
+/// `Input`, with the position of the selected match among all matches.
+pub fn input_title(highlight: Highlight, selected: Option<(usize, usize)>) -> String {
+    let title = match highlight {
+        Highlight::Matches => "Input",
+        Highlight::Groups => "Input (groups)",
+    };
+    match selected {
+        Some((i, count)) => format!("{}, match {} of {}", title, i + 1, count),
+        None => title.to_string(),
+    }
+}
+
+/// Line and column, both counted from 1, of a byte offset in the body.
+pub fn position(body: &str, offset: usize) -> (usize, usize) {
+    let before = &body[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// The body with the segments styled, segments are sorted and do not overlap.
+pub fn segmented(body: &str, segments: Vec<(usize, usize, Style)>) -> Text<'static> {
+    fn append_match(part: &str, lines: &mut Vec<Vec<Span<'static>>>, style: Style) {
+        let last = lines.len() - 1;
+        if !part.contains('\n') {
+            lines[last].push(Span::styled(part.to_owned(), style));
+            return;
+        }
+
+        for (last, p) in (last..).zip(part.split('\n')) {
+            lines[last].push(Span::styled(p.to_owned(), style));
+            lines.push(vec![]);
+        }
+
+        lines.pop();
+    }
+    let mut lines: Vec<Vec<Span>> = vec![vec![]];
+    let mut current_index = 0;
+
+    for (start, end, style) in segments {
+        append_match(&body[current_index..start], &mut lines, Style::default());
+        append_match(&body[start..end], &mut lines, style);
+        current_index = end;
+    }
+    append_match(&body[current_index..], &mut lines, Style::default());
+    let mut text = Text::default();
+    for line in lines {
+        text.push_line(Line::from(line));
+    }
+    text
+}
+
+/// The matches overlapping the window, with their index. Matches are sorted and do not
+/// overlap, so the first one is found by bisecting.
+fn in_window<'a>(
+    found: &'a [Captures],
+    window: &'a Range<usize>,
+) -> impl Iterator<Item = (usize, &'a Captures)> + 'a {
+    let first =
+        found.partition_point(|caps| caps[0].as_ref().is_some_and(|m| m.end < window.start));
+    found
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take_while(|(_, caps)| caps[0].as_ref().is_some_and(|m| m.start <= window.end))
+}
+
+/// Every match overlapping the window as one segment, cycling through the colors per match.
+fn match_segments(found: &[Captures], window: &Range<usize>) -> Vec<(usize, usize, Style)> {
+    in_window(found, window)
+        .filter_map(|(i, caps)| caps[0].clone().map(|m| (i, m)))
+        .map(|(i, m)| {
+            (
+                m.start,
+                m.end,
+                Style::default().fg(Color::Black).bg(get_color(i)),
+            )
+        })
+        .collect()
+}
+
+/// Split every match on the boundaries of its groups. A segment gets the color of the
+/// innermost group covering it, segments inside nested groups are underlined as well and
+/// parts of the match outside any group are only underlined. Only matches overlapping the
+/// window are split.
+fn group_segments(found: &[Captures], window: &Range<usize>) -> Vec<(usize, usize, Style)> {
+    let mut segments = vec![];
+    // End of the last segment, `segmented` needs them in order and apart.
+    let mut cursor = 0;
+    for (_, caps) in in_window(found, window) {
+        let Some(whole) = caps[0].clone() else {
+            continue;
+        };
+        // A group captured in a lookaround can lie outside the match, only the part within
+        // it is shown.
+        let groups = caps
+            .iter()
             .enumerate()
-            .map(|(i, x)| match x {
-                Some(name) => name.to_string(),
-                None => i.to_string(),
+            .skip(1)
+            .filter_map(|(i, m)| m.as_ref().map(|m| (i, m.start, m.end)))
+            .map(|(i, start, end)| {
+                let start = start.clamp(whole.start, whole.end);
+                (i, start, end.clamp(start, whole.end))
             })
             .collect::<Vec<_>>();
 
+        let mut boundaries = vec![whole.start, whole.end];
+        for (_, start, end) in &groups {
+            boundaries.push(*start);
+            boundaries.push(*end);
+        }
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        for pair in boundaries.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            if start < cursor {
+                continue;
+            }
+            let covering = groups
+                .iter()
+                .filter(|(_, s, e)| *s <= start && *e >= end)
+                .map(|(i, _, _)| *i);
+            let depth = covering.clone().count();
+            let style = match covering.max() {
+                None => Style::default().add_modifier(Modifier::UNDERLINED),
+                Some(i) if depth == 1 => Style::default().fg(Color::Black).bg(get_color(i)),
+                Some(i) => Style::default()
+                    .fg(Color::Black)
+                    .bg(get_color(i))
+                    .add_modifier(Modifier::UNDERLINED | Modifier::BOLD),
+            };
+            segments.push((start, end, style));
+            cursor = end;
+        }
+    }
+    segments
+}
+
+/// Group names, or their number for unnamed groups.
+fn group_names(reg: &Regex) -> Vec<String> {
+    reg.capture_names()
+        .into_iter()
+        .enumerate()
+        .map(|(i, x)| match x {
+            Some(name) => name,
+            None => i.to_string(),
+        })
+        .collect()
+}
+
+/// The captures table as tab separated values, a header with the group names and a row per
+/// match.
+pub fn captures_table(reg: &Regex, body: &str, found: &[Captures]) -> String {
+    let mut rows = vec![group_names(reg).join("\t")];
+    for cap in found {
+        rows.push(
+            cap.iter()
+                .map(|sub| sub.clone().map_or("", |sub| &body[sub]))
+                .collect::<Vec<_>>()
+                .join("\t"),
+        );
+    }
+    rows.join("\n")
+}
+
+pub fn captures(
+    reg: Option<&Regex>,
+    body: &str,
+    found: &[Captures],
+    highlight: Highlight,
+) -> impl Widget {
+    if let Some(reg) = reg {
+        let mut rows: Vec<Row> = vec![];
+        let names = group_names(reg);
+
         let widths = vec![Constraint::Fill(1); names.len()];
 
-        for (i, cap) in reg.captures_iter(&body).enumerate() {
-            rows.push(
-                Row::new(cap.iter().map(|sub| match sub {
-                    Some(sub) => sub.as_str().to_string(),
-                    None => "".to_string(),
-                }))
-                .style(Style::default().fg(get_color(i))),
-            )
+        for (i, cap) in found.iter().enumerate() {
+            let cells = cap.iter().map(|sub| match sub {
+                Some(sub) => body[sub.clone()].to_string(),
+                None => "".to_string(),
+            });
+            rows.push(match highlight {
+                Highlight::Matches => Row::new(cells).style(Style::default().fg(get_color(i))),
+                Highlight::Groups => Row::new(
+                    cells
+                        .enumerate()
+                        .map(|(group, cell)| Cell::new(cell).style(group_column_style(group))),
+                ),
+            })
         }
+        let names = names
+            .into_iter()
+            .enumerate()
+            .map(|(group, name)| match highlight {
+                Highlight::Matches => Cell::new(name),
+                Highlight::Groups => Cell::new(name).style(group_column_style(group)),
+            });
 
         Table::new(rows, widths)
             .column_spacing(1)
@@ -137,12 +504,85 @@ pub fn captures(reg: Option<Regex>, body: String) -> impl Widget {
     }
 }
 
-pub fn substitution(body: String, reg: Option<Regex>, substitution: String) -> impl Widget {
-    let body = match reg {
-        Some(regex) => regex.replace_all(&body, &substitution).to_string(),
-        None => body,
-    };
-    Paragraph::new(body).block(
+fn group_column_style(group: usize) -> Style {
+    match group {
+        0 => Style::default(),
+        group => Style::default().fg(get_color(group)),
+    }
+}
+
+/// The byte range, position and group values of the match with the index.
+pub fn match_detail(
+    reg: Option<&Regex>,
+    body: &str,
+    found: &[Captures],
+    index: Option<usize>,
+) -> impl Widget {
+    let mut rows = vec![];
+    let mut title = "Match".to_string();
+    if let (Some(reg), Some(index)) = (reg, index) {
+        if let Some(caps) = found.get(index) {
+            title = format!("Match {} of {}", index + 1, found.len());
+            let whole = caps[0].clone().unwrap_or_default();
+            let at = |offset: usize| {
+                let (line, column) = position(body, offset);
+                format!("line {}, column {}", line, column)
+            };
+            rows.push(Row::new(vec![
+                "bytes".to_string(),
+                format!("{}..{}", whole.start, whole.end),
+            ]));
+            rows.push(Row::new(vec!["start".to_string(), at(whole.start)]));
+            rows.push(Row::new(vec!["end".to_string(), at(whole.end)]));
+            for (group, (name, sub)) in group_names(reg)
+                .into_iter()
+                .zip(caps.iter().cloned())
+                .enumerate()
+            {
+                let cells = match sub {
+                    Some(Range { start, end }) => vec![
+                        Cell::new(name),
+                        Cell::new(format!("{:?}", &body[start..end])),
+                        Cell::new(format!("{}..{}", start, end)),
+                    ],
+                    None => vec![Cell::new(name), Cell::new("did not participate")],
+                };
+                let row = Row::new(cells).style(group_column_style(group));
+                rows.push(if group == 0 { row.top_margin(1) } else { row });
+            }
+        }
+    }
+    Table::new(
+        rows,
+        vec![
+            Constraint::Length(8),
+            Constraint::Fill(1),
+            Constraint::Length(12),
+        ],
+    )
+    .column_spacing(1)
+    .block(
+        Block::new()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Gray))
+            .borders(Borders::ALL)
+            .title(title),
+    )
+}
+
+pub fn substitute(
+    body: &str,
+    reg: Option<&Regex>,
+    substitution: String,
+) -> Result<String, engine::Error> {
+    match reg {
+        Some(regex) => regex.replace_all(body, &substitution),
+        None => Ok(body.to_string()),
+    }
+}
+
+pub fn substitution(output: &str) -> impl Widget + '_ {
+    Paragraph::new(output).block(
         Block::new()
             .border_type(BorderType::Rounded)
             .border_style(Style::default().fg(Color::Gray))
@@ -150,3 +590,92 @@ pub fn substitution(body: String, reg: Option<Regex>, substitution: String) -> i
             .title("Output"),
     )
 }
+
+pub fn split(body: &str, reg: Option<&Regex>, limit: Option<usize>) -> Vec<String> {
+    match reg {
+        Some(regex) => regex
+            .split(body, limit)
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        None => vec![body.to_string()],
+    }
+}
+
+pub fn fields(fields: &[String], nul_separated: bool) -> impl Widget {
+    let rows = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            Row::new(vec![
+                Cell::new((i + 1).to_string()).style(Style::default().fg(Color::Gray)),
+                Cell::new(field.escape_debug().to_string()),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let width = rows.len().to_string().len() as u16;
+
+    Table::new(rows, vec![Constraint::Length(width), Constraint::Fill(1)])
+        .column_spacing(2)
+        .block(
+            Block::new()
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Gray))
+                .borders(Borders::ALL)
+                .title(if nul_separated {
+                    "Fields (NUL separated)"
+                } else {
+                    "Fields"
+                }),
+        )
+}
+
+/// The text of the group in every match found by the regex, matches where the group did not
+/// participate are skipped. Fails when the regex has no such group.
+pub fn extract(
+    body: &str,
+    reg: Option<&Regex>,
+    found: &[Captures],
+    group: &str,
+) -> Result<Vec<String>, String> {
+    let Some(regex) = reg else {
+        return Ok(vec![]);
+    };
+    let Some(index) = regex.group_index(group) else {
+        return Err(format!("no group '{}'", group.trim()));
+    };
+    Ok(found
+        .iter()
+        .filter_map(|caps| caps[index].clone())
+        .map(|range| body[range].to_string())
+        .collect())
+}
+
+pub fn extracted(body: &str, reg: Option<&Regex>, found: &[Captures], group: &str) -> impl Widget {
+    let block = Block::new()
+        .border_type(BorderType::Rounded)
+        .borders(Borders::ALL);
+    match extract(body, reg, found, group) {
+        Ok(matches) => {
+            let count = format!(
+                "{} match{}",
+                matches.len(),
+                if matches.len() == 1 { "" } else { "es" }
+            );
+            let title = match group.trim() {
+                "" => format!("Extracted, {}", count),
+                group => format!("Extracted group {}, {}", group, count),
+            };
+            Paragraph::new(matches.join("\n")).block(
+                block
+                    .border_style(Style::default().fg(Color::Gray))
+                    .title(title),
+            )
+        }
+        Err(err) => Paragraph::new("").block(
+            block
+                .border_style(Style::default().fg(Color::LightRed))
+                .title(format!("Extracted: {}", err)),
+        ),
+    }
+}
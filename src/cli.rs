@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+
+use clap::{parser::ValueSource, ArgGroup, CommandFactory, FromArgMatches, Parser};
+
+use crate::{
+    app::Mode,
+    engine::Engine,
+    filter::{Context, FilterOptions},
+    flags::Flags,
+};
+
+#[derive(Parser)]
+#[command(version, about)]
+#[command(group(ArgGroup::new("source").args(["regex", "patterns", "tests"]).multiple(true)))]
+pub struct Args {
+    /// Regex to start with
+    #[arg(short, long)]
+    pub regex: Option<String>,
+
+    /// File with one regex per line to start set mode with, or to compare with the regex in bench mode
+    #[arg(short = 'P', long)]
+    pub patterns: Option<PathBuf>,
+
+    /// Start with the pattern that was exported or saved last
+    #[arg(long, conflicts_with = "regex")]
+    pub last: bool,
+
+    /// Regex flags in inline syntax, e.g. `im` or `i-u`
+    #[arg(short = 'F', long, default_value_t = Flags::default(), hide_default_value = true)]
+    pub flags: Flags,
+
+    /// Regex engine to start with
+    #[arg(short, long, value_enum, default_value_t = Engine::Regex)]
+    pub engine: Engine,
+
+    /// Substitution string to start with
+    #[arg(short, long)]
+    pub sub: Option<String>,
+
+    /// Maximum number of fields in split mode
+    #[arg(short, long)]
+    pub limit: Option<usize>,
+
+    /// Show the lines not matching the regex in filter mode
+    #[arg(short = 'v', long)]
+    pub invert: bool,
+
+    /// Lines of context in filter mode, e.g. `2` or `1,3` for one before and three after
+    #[arg(short = 'C', long)]
+    pub context: Option<Context>,
+
+    /// Number the lines in filter mode
+    #[arg(short = 'n', long)]
+    pub line_numbers: bool,
+
+    /// Group number or name to extract in extract mode, the whole match by default
+    #[arg(short, long)]
+    pub group: Option<String>,
+
+    /// Times every pattern searches the input in bench mode, 10 by default
+    #[arg(long)]
+    pub runs: Option<usize>,
+
+    /// Mode to start in
+    #[arg(short, long, value_enum, default_value_t = Mode::Substitution)]
+    pub mode: Mode,
+
+    /// Read the input from a file instead of stdin
+    #[arg(short, long)]
+    pub file: Option<PathBuf>,
+
+    /// Test suite to load and save with ^s, in batch mode only the tests are run
+    #[arg(short, long)]
+    pub tests: Option<PathBuf>,
+
+    /// Session to start from and save with alt+w, the other arguments and the input replace
+    /// what it stored
+    #[arg(short = 'S', long)]
+    pub session: Option<PathBuf>,
+
+    /// Apply the regex to the input and print the output without opening the TUI
+    #[arg(short, long, requires = "source")]
+    pub batch: bool,
+
+    /// Ids of the arguments given on the command line, rather than taking their default.
+    #[arg(skip)]
+    given: Vec<String>,
+}
+
+impl Args {
+    pub fn parse_given() -> Self {
+        let matches = Self::command().get_matches();
+        let mut args = Self::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+        args.given = matches
+            .ids()
+            .filter(|id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine))
+            .map(|id| id.to_string())
+            .collect();
+        args
+    }
+
+    /// Whether the argument was given on the command line.
+    pub fn given(&self, id: &str) -> bool {
+        self.given.iter().any(|given| given == id)
+    }
+
+    pub fn filter(&self) -> FilterOptions {
+        FilterOptions {
+            invert: self.invert,
+            line_numbers: self.line_numbers,
+            context: self.context.unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::{env, io};
+
+use ratatui::{
+    buffer::Buffer,
+    crossterm::{clipboard::CopyToClipboard, Command},
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Widget},
+};
+
+/// Set the clipboard with an OSC 52 escape sequence. The terminal does the copying, so it
+/// works over SSH without a clipboard daemon. In tmux the sequence is passed through to the
+/// terminal outside, tmux keeps it to itself otherwise.
+pub fn copy(out: &mut impl io::Write, text: &str) -> io::Result<()> {
+    let mut sequence = String::new();
+    CopyToClipboard::to_clipboard_from(text)
+        .write_ansi(&mut sequence)
+        .map_err(io::Error::other)?;
+    if env::var_os("TMUX").is_some() {
+        sequence = format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+    }
+    out.write_all(sequence.as_bytes())?;
+    out.flush()
+}
+
+#[derive(Clone, Copy)]
+pub enum Target {
+    Regex,
+    Output,
+    Captures,
+    Match,
+}
+
+/// Targets in the order they are shown in the copy menu.
+pub const TARGETS: [Target; 4] = [
+    Target::Regex,
+    Target::Output,
+    Target::Captures,
+    Target::Match,
+];
+
+impl Target {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Target::Regex => "regex",
+            Target::Output => "output",
+            Target::Captures => "captures table",
+            Target::Match => "current match",
+        }
+    }
+}
+
+/// The alt+c overlay to pick what to copy.
+#[derive(Default)]
+pub struct CopyMenu {
+    selected: usize,
+}
+
+impl CopyMenu {
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self) {
+        self.selected = (self.selected + 1).min(TARGETS.len() - 1);
+    }
+
+    pub fn current(&self) -> Target {
+        TARGETS[self.selected]
+    }
+
+    pub fn widget(&self) -> impl Widget + '_ {
+        let lines = TARGETS
+            .iter()
+            .enumerate()
+            .map(|(i, target)| {
+                let line = Line::raw(target.name());
+                match i == self.selected {
+                    true => line.style(Style::default().add_modifier(Modifier::REVERSED)),
+                    false => line,
+                }
+            })
+            .collect::<Vec<_>>();
+        struct Menu(Paragraph<'static>);
+
+        impl Widget for Menu {
+            fn render(self, area: Rect, buf: &mut Buffer) {
+                Clear.render(area, buf);
+                self.0.render(area, buf);
+            }
+        }
+
+        Menu(
+            Paragraph::new(lines).block(
+                Block::new()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::Cyan))
+                    .borders(Borders::ALL)
+                    .title("Copy (enter copy, esc cancel)"),
+            ),
+        )
+    }
+}
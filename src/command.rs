@@ -0,0 +1,479 @@
+use std::{collections::HashMap, ops::Range};
+
+use regex_syntax::ast::{
+    parse::ParserBuilder, AssertionKind, Ast, ClassPerl, ClassPerlKind, ClassSet, ClassSetItem,
+    Flag, FlagsItemKind, GroupKind, HexLiteralKind, Literal, LiteralKind,
+};
+
+use crate::{
+    engine::Engine,
+    export::{parse_replacement, Part},
+    flags::Flags,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Dialect {
+    /// GNU sed with extended regular expressions.
+    Sed,
+    Perl,
+}
+
+/// Rewrites a regex crate pattern for a dialect, noting what it can not express.
+struct Translator {
+    dialect: Dialect,
+    edits: Vec<(Range<usize>, String)>,
+    warnings: Vec<String>,
+    /// Groups in the translated pattern, sed turns non-capturing groups into capturing ones.
+    groups: usize,
+    /// Group number in the translated pattern by the original number or name.
+    indices: HashMap<String, usize>,
+}
+
+impl Translator {
+    fn translate(dialect: Dialect, regex: &str, flags: &Flags) -> Self {
+        let mut translator = Translator {
+            dialect,
+            edits: vec![],
+            warnings: vec![],
+            groups: 0,
+            indices: HashMap::new(),
+        };
+        match ParserBuilder::new()
+            .ignore_whitespace(flags.ignore_whitespace)
+            .build()
+            .parse(regex)
+        {
+            Ok(ast) => translator.ast(&ast),
+            Err(_) if dialect == Dialect::Sed => {
+                translator.warn("sed has no look-around, the pattern is left as it is".to_string())
+            }
+            // Perl has everything fancy-regex adds.
+            Err(_) => {}
+        }
+        translator
+    }
+
+    fn name(&self) -> &'static str {
+        match self.dialect {
+            Dialect::Sed => "sed",
+            Dialect::Perl => "perl",
+        }
+    }
+
+    fn edit(&mut self, range: Range<usize>, replacement: &str) {
+        self.edits.push((range, replacement.to_string()));
+    }
+
+    fn warn(&mut self, warning: String) {
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+
+    /// The pattern with the edits applied, they never overlap.
+    fn apply(&mut self, regex: &str) -> String {
+        self.edits.sort_by_key(|(range, _)| range.start);
+        let (mut out, mut end) = (String::new(), 0);
+        for (range, replacement) in &self.edits {
+            out.push_str(&regex[end..range.start]);
+            out.push_str(replacement);
+            end = range.end;
+        }
+        out.push_str(&regex[end..]);
+        out
+    }
+
+    fn ast(&mut self, ast: &Ast) {
+        match ast {
+            Ast::Empty(_) | Ast::Dot(_) => {}
+            Ast::Flags(set) => {
+                let flags = &set.flags.items;
+                match self.dialect {
+                    Dialect::Sed => self.warn("sed has no inline flags".to_string()),
+                    Dialect::Perl => {
+                        for item in flags {
+                            self.flag(&item.kind);
+                        }
+                    }
+                }
+            }
+            Ast::Literal(literal) => self.literal(literal, false),
+            Ast::Assertion(assertion) => {
+                let span = assertion.span.start.offset..assertion.span.end.offset;
+                let replacement = match (self.dialect, &assertion.kind) {
+                    (Dialect::Sed, AssertionKind::StartText) => Some("^"),
+                    (Dialect::Sed, AssertionKind::EndText) => Some("$"),
+                    (
+                        Dialect::Sed,
+                        AssertionKind::WordBoundaryStart | AssertionKind::WordBoundaryStartAngle,
+                    ) => Some(r"\<"),
+                    (
+                        Dialect::Sed,
+                        AssertionKind::WordBoundaryEnd | AssertionKind::WordBoundaryEndAngle,
+                    ) => Some(r"\>"),
+                    (
+                        Dialect::Sed,
+                        AssertionKind::WordBoundaryStartHalf | AssertionKind::WordBoundaryEndHalf,
+                    ) => {
+                        self.warn("sed has no half word boundaries".to_string());
+                        None
+                    }
+                    (
+                        Dialect::Perl,
+                        AssertionKind::WordBoundaryStart | AssertionKind::WordBoundaryStartAngle,
+                    ) => Some(r"\b(?=\w)"),
+                    (
+                        Dialect::Perl,
+                        AssertionKind::WordBoundaryEnd | AssertionKind::WordBoundaryEndAngle,
+                    ) => Some(r"\b(?<=\w)"),
+                    (Dialect::Perl, AssertionKind::WordBoundaryStartHalf) => Some(r"(?<!\w)"),
+                    (Dialect::Perl, AssertionKind::WordBoundaryEndHalf) => Some(r"(?!\w)"),
+                    _ => None,
+                };
+                if let Some(replacement) = replacement {
+                    self.edit(span, replacement);
+                }
+            }
+            Ast::ClassUnicode(_) => {
+                if self.dialect == Dialect::Sed {
+                    self.warn(r"sed has no Unicode classes like \pL".to_string());
+                }
+            }
+            Ast::ClassPerl(class) => {
+                if self.dialect == Dialect::Sed && class.kind == ClassPerlKind::Digit {
+                    let replacement = if class.negated { "[^0-9]" } else { "[0-9]" };
+                    self.edit(class.span.start.offset..class.span.end.offset, replacement);
+                }
+            }
+            Ast::ClassBracketed(class) => self.class_set(&class.kind),
+            Ast::Repetition(repetition) => {
+                if !repetition.greedy && self.dialect == Dialect::Sed {
+                    self.warn("sed has no lazy repetitions, they are greedy".to_string());
+                }
+                self.ast(&repetition.ast);
+            }
+            Ast::Group(group) => {
+                let open = group.span.start.offset..group.ast.span().start.offset;
+                match &group.kind {
+                    GroupKind::CaptureIndex(i) => {
+                        self.groups += 1;
+                        self.indices.insert(i.to_string(), self.groups);
+                    }
+                    GroupKind::CaptureName { name, .. } => {
+                        self.groups += 1;
+                        self.indices.insert(name.index.to_string(), self.groups);
+                        self.indices.insert(name.name.clone(), self.groups);
+                        if self.dialect == Dialect::Sed {
+                            self.edit(open, "(");
+                        }
+                    }
+                    GroupKind::NonCapturing(flags) => match self.dialect {
+                        Dialect::Sed => {
+                            if !flags.items.is_empty() {
+                                self.warn("sed has no inline flags".to_string());
+                            }
+                            self.groups += 1;
+                            self.edit(open, "(");
+                        }
+                        Dialect::Perl => {
+                            for item in &flags.items {
+                                self.flag(&item.kind);
+                            }
+                        }
+                    },
+                }
+                self.ast(&group.ast);
+            }
+            Ast::Alternation(alternation) => {
+                for ast in &alternation.asts {
+                    self.ast(ast);
+                }
+            }
+            Ast::Concat(concat) => {
+                for ast in &concat.asts {
+                    self.ast(ast);
+                }
+            }
+        }
+    }
+
+    fn flag(&mut self, kind: &FlagsItemKind) {
+        let name = match kind {
+            FlagsItemKind::Flag(Flag::SwapGreed) => "U",
+            FlagsItemKind::Flag(Flag::CRLF) => "R",
+            FlagsItemKind::Flag(Flag::Unicode) => "u",
+            _ => return,
+        };
+        self.warn(format!("{} has no inline flag {}", self.name(), name));
+    }
+
+    fn literal(&mut self, literal: &Literal, bracketed: bool) {
+        let span = literal.span.start.offset..literal.span.end.offset;
+        let c = literal.c;
+        match (self.dialect, &literal.kind) {
+            (Dialect::Sed, LiteralKind::Meta | LiteralKind::Superfluous) if bracketed => match c {
+                ']' | '-' | '^' | '\\' => self.warn(format!(
+                    "sed can not escape {} in a bracket class, move it to the start or end",
+                    c
+                )),
+                c => self.edit(span, &c.to_string()),
+            },
+            // Escapes the regex crate allows on any punctuation, sed only knows some of them.
+            (Dialect::Sed, LiteralKind::Superfluous) if c != '/' => self.edit(span, &c.to_string()),
+            (
+                Dialect::Sed,
+                LiteralKind::HexFixed(HexLiteralKind::UnicodeShort | HexLiteralKind::UnicodeLong)
+                | LiteralKind::HexBrace(_),
+            ) => match c.is_ascii() {
+                true => self.edit(span, &format!("\\x{:02x}", c as u32)),
+                false => self.edit(span, &c.to_string()),
+            },
+            (
+                Dialect::Perl,
+                LiteralKind::HexFixed(HexLiteralKind::UnicodeShort | HexLiteralKind::UnicodeLong),
+            ) => self.edit(span, &format!("\\x{{{:x}}}", c as u32)),
+            _ => {}
+        }
+    }
+
+    fn class_set(&mut self, set: &ClassSet) {
+        match set {
+            ClassSet::Item(item) => self.class_set_item(item),
+            ClassSet::BinaryOp(op) => {
+                self.warn(format!(
+                    "{} has no class set operations like && and --",
+                    self.name()
+                ));
+                self.class_set(&op.lhs);
+                self.class_set(&op.rhs);
+            }
+        }
+    }
+
+    fn class_set_item(&mut self, item: &ClassSetItem) {
+        match item {
+            ClassSetItem::Empty(_) | ClassSetItem::Ascii(_) => {}
+            ClassSetItem::Literal(literal) => self.literal(literal, true),
+            ClassSetItem::Range(range) => {
+                self.literal(&range.start, true);
+                self.literal(&range.end, true);
+            }
+            ClassSetItem::Unicode(_) => {
+                if self.dialect == Dialect::Sed {
+                    self.warn(r"sed has no Unicode classes like \pL".to_string());
+                }
+            }
+            ClassSetItem::Perl(class) => self.bracketed_perl(class),
+            ClassSetItem::Bracketed(class) => self.class_set(&class.kind),
+            ClassSetItem::Union(union) => {
+                for item in &union.items {
+                    self.class_set_item(item);
+                }
+            }
+        }
+    }
+
+    /// sed reads `\d` in a bracket class as a backslash and a `d`.
+    fn bracketed_perl(&mut self, class: &ClassPerl) {
+        if self.dialect != Dialect::Sed {
+            return;
+        }
+        if class.negated {
+            self.warn(r"sed has no negated classes like \D in a bracket class".to_string());
+            return;
+        }
+        let replacement = match class.kind {
+            ClassPerlKind::Digit => "0-9",
+            ClassPerlKind::Space => "[:space:]",
+            ClassPerlKind::Word => "[:alnum:]_",
+        };
+        self.edit(class.span.start.offset..class.span.end.offset, replacement);
+    }
+
+    /// The group number in the translated pattern, unknown groups are replaced by nothing.
+    fn index(&self, group: &str) -> Option<usize> {
+        self.indices
+            .get(group)
+            .copied()
+            .or_else(|| group.parse().ok())
+    }
+}
+
+/// Escape the `/` delimiter of `s/…/…/` where it is not escaped yet.
+fn delimited(regex: &str) -> String {
+    let mut out = String::new();
+    let mut chars = regex.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                out.push(c);
+                out.extend(chars.next());
+            }
+            '/' => out.push_str(r"\/"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Keep Perl from interpolating variables into the pattern. `@` is escaped everywhere and `$`
+/// in classes, an end anchor followed by anything but `)`, `|` or the end of the pattern is
+/// wrapped in a group so it is not read as a variable like `$foo` or `$(`.
+fn uninterpolated(regex: &str) -> String {
+    let mut out = String::new();
+    let mut chars = regex.chars().peekable();
+    // Depth of nested classes, the regex crate allows classes within classes.
+    let mut class = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                out.push(c);
+                out.extend(chars.next());
+            }
+            '[' => {
+                class += 1;
+                out.push(c);
+                // A `]` right after the opening bracket, or its negation, is literal.
+                if chars.peek() == Some(&'^') {
+                    out.extend(chars.next());
+                }
+                if chars.peek() == Some(&']') {
+                    out.extend(chars.next());
+                }
+            }
+            ']' if class > 0 => {
+                class -= 1;
+                out.push(c);
+            }
+            '@' => out.push_str(r"\@"),
+            '$' if class > 0 => out.push_str(r"\$"),
+            '$' if matches!(chars.peek(), None | Some(')') | Some('|')) => out.push(c),
+            '$' => out.push_str("(?:$)"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// A single quoted shell word.
+pub fn shell_quoted(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// `sed -E` with the pattern in extended regular expressions, warnings for what GNU sed can
+/// not express.
+pub fn sed(regex: &str, flags: &Flags, substitution: Option<&str>) -> (String, Vec<String>) {
+    let mut translator = Translator::translate(Dialect::Sed, regex, flags);
+    let pattern = delimited(&translator.apply(regex));
+    let mut modifiers = String::new();
+    if flags.case_insensitive {
+        modifiers.push('I');
+    }
+    if flags.multi_line {
+        modifiers.push('M');
+    }
+    let command = match substitution {
+        Some(sub) => {
+            let mut replacement = String::new();
+            for part in parse_replacement(sub) {
+                match part {
+                    Part::Literal(s) => {
+                        for c in s.chars() {
+                            match c {
+                                '\\' | '&' | '/' => {
+                                    replacement.push('\\');
+                                    replacement.push(c);
+                                }
+                                '\n' => replacement.push_str(r"\n"),
+                                c => replacement.push(c),
+                            }
+                        }
+                    }
+                    Part::Group(g) if g == "0" => replacement.push('&'),
+                    Part::Group(g) => match translator.index(&g) {
+                        Some(i) if i <= 9 => replacement.push_str(&format!("\\{}", i)),
+                        Some(_) => translator.warn(format!(
+                            "sed only refers to groups \\1 to \\9, {} is left out",
+                            g
+                        )),
+                        None => {}
+                    },
+                }
+            }
+            format!(
+                "sed -E {}",
+                shell_quoted(&format!("s/{}/{}/g{}", pattern, replacement, modifiers))
+            )
+        }
+        None => format!(
+            "sed -nE {}",
+            shell_quoted(&format!("/{}/{}p", pattern, modifiers))
+        ),
+    };
+    (command, translator.warnings)
+}
+
+/// `perl -pe`, Perl reads nearly all of the syntax as it is.
+pub fn perl(regex: &str, flags: &Flags, substitution: Option<&str>) -> (String, Vec<String>) {
+    let mut translator = Translator::translate(Dialect::Perl, regex, flags);
+    let pattern = delimited(&uninterpolated(&translator.apply(regex)));
+    let modifiers = "imsx"
+        .chars()
+        .filter(|flag| flags.is_set(*flag))
+        .collect::<String>();
+    let command = match substitution {
+        Some(sub) => {
+            let mut replacement = String::new();
+            for part in parse_replacement(sub) {
+                match part {
+                    Part::Literal(s) => {
+                        for c in s.chars() {
+                            if matches!(c, '\\' | '/' | '$' | '@') {
+                                replacement.push('\\');
+                            }
+                            replacement.push(c);
+                        }
+                    }
+                    Part::Group(g) if g == "0" => replacement.push_str("$&"),
+                    Part::Group(g) if g.chars().all(|c| c.is_ascii_digit()) => {
+                        replacement.push_str(&format!("${{{}}}", g))
+                    }
+                    Part::Group(g) => replacement.push_str(&format!("$+{{{}}}", g)),
+                }
+            }
+            format!(
+                "perl -pe {}",
+                shell_quoted(&format!("s/{}/{}/g{}", pattern, replacement, modifiers))
+            )
+        }
+        None => format!(
+            "perl -ne {}",
+            shell_quoted(&format!("print if /{}/{}", pattern, modifiers))
+        ),
+    };
+    (command, translator.warnings)
+}
+
+/// `rg`, which uses the regex crate, or PCRE2 for fancy-regex patterns.
+pub fn ripgrep(
+    regex: &str,
+    flags: &Flags,
+    engine: Engine,
+    substitution: Option<&str>,
+) -> (String, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut command = String::from("rg");
+    if Engine::Regex.build(regex, flags).is_err() && engine == Engine::Fancy {
+        command.push_str(" --pcre2");
+        warnings.push("rg needs to be built with PCRE2 for look-around".to_string());
+    }
+    if let Some(sub) = substitution {
+        command = format!("{} --passthru --replace {}", command, shell_quoted(sub));
+    }
+    command = format!(
+        "{} -e {}",
+        command,
+        shell_quoted(&format!("{}{}", flags.inline(), regex))
+    );
+    (command, warnings)
+}
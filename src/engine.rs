@@ -0,0 +1,283 @@
+use std::{fmt, ops::Range};
+
+use clap::ValueEnum;
+use regex_automata::nfa::thompson;
+use regex_syntax::hir::literal::Extractor;
+use serde::{Deserialize, Serialize};
+
+use crate::flags::Flags;
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    /// The regex crate, linear time but no lookaround or backreferences
+    #[default]
+    Regex,
+    /// fancy-regex, adds lookaround and backreferences by backtracking
+    Fancy,
+}
+
+impl Engine {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Engine::Regex => "regex",
+            Engine::Fancy => "fancy-regex",
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
+        match self {
+            Engine::Regex => Engine::Fancy,
+            Engine::Fancy => Engine::Regex,
+        }
+    }
+
+    pub fn build(&self, pattern: &str, flags: &Flags) -> Result<Regex, Error> {
+        match self {
+            Engine::Regex => regex::RegexBuilder::new(pattern)
+                .case_insensitive(flags.case_insensitive)
+                .multi_line(flags.multi_line)
+                .dot_matches_new_line(flags.dot_matches_new_line)
+                .ignore_whitespace(flags.ignore_whitespace)
+                .swap_greed(flags.swap_greed)
+                .unicode(flags.unicode)
+                .crlf(flags.crlf)
+                .build()
+                .map(Regex::Regex)
+                .map_err(Error::Regex),
+            Engine::Fancy => {
+                // fancy-regex has no builder option to swap greed, the inline flag does the same.
+                let pattern = if flags.swap_greed {
+                    format!("{}{}", FANCY_SWAP_GREED, pattern)
+                } else {
+                    pattern.to_string()
+                };
+                fancy_regex::RegexBuilder::new(&pattern)
+                    .case_insensitive(flags.case_insensitive)
+                    .multi_line(flags.multi_line)
+                    .dot_matches_new_line(flags.dot_matches_new_line)
+                    .ignore_whitespace(flags.ignore_whitespace)
+                    .unicode_mode(flags.unicode)
+                    .crlf(flags.crlf)
+                    .build()
+                    .map(Regex::Fancy)
+                    .map_err(|err| Error::Fancy(err, flags.swap_greed))
+            }
+        }
+    }
+}
+
+const FANCY_SWAP_GREED: &str = "(?U)";
+
+/// What the regex crate compiles a pattern to.
+pub struct Program {
+    /// Heap size in bytes of the Thompson NFA, every engine of the regex crate starts from it.
+    pub size: usize,
+    /// The literals every match starts with, a search scans for these before running the
+    /// regex. Empty when there are none or too many to be worth it.
+    pub prefixes: Vec<String>,
+}
+
+impl Program {
+    /// None for patterns the regex crate does not support.
+    pub fn new(pattern: &str, flags: &Flags) -> Option<Self> {
+        let hir = flags.parser().parse(pattern).ok()?;
+        let nfa = thompson::Compiler::new().build_from_hir(&hir).ok()?;
+        let mut prefixes = Extractor::new().extract(&hir);
+        prefixes.optimize_for_prefix_by_preference();
+        let prefixes = match prefixes.literals() {
+            Some(literals) if literals.iter().all(|l| !l.as_bytes().is_empty()) => literals
+                .iter()
+                .map(|l| String::from_utf8_lossy(l.as_bytes()).into_owned())
+                .collect(),
+            _ => vec![],
+        };
+        Some(Self {
+            size: nfa.memory_usage(),
+            prefixes,
+        })
+    }
+}
+
+pub enum Error {
+    Regex(regex::Error),
+    /// The flag tells whether the swap greed prefix was added to the pattern.
+    Fancy(fancy_regex::Error, bool),
+}
+
+impl Error {
+    /// Char offset in the pattern for errors that point at a position.
+    pub fn position(&self, pattern: &str) -> Option<usize> {
+        match self {
+            Error::Fancy(fancy_regex::Error::ParseError(pos, _), prefixed) => {
+                let pos = if *prefixed {
+                    pos.saturating_sub(FANCY_SWAP_GREED.len())
+                } else {
+                    *pos
+                };
+                Some(pattern[..pos.min(pattern.len())].chars().count())
+            }
+            _ => None,
+        }
+    }
+
+    /// The size limit if the pattern is valid but compiled too big.
+    pub fn size_limit(&self) -> Option<usize> {
+        match self {
+            Error::Regex(regex::Error::CompiledTooBig(limit)) => Some(*limit),
+            Error::Fancy(fancy_regex::Error::CompileError(err), _) => match err.as_ref() {
+                fancy_regex::CompileError::InnerError(err) => err.size_limit(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Regex(err) => write!(f, "{}", err),
+            Error::Fancy(err, _) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// The byte ranges of all groups of a match, group 0 being the whole match.
+pub type Captures = Vec<Option<Range<usize>>>;
+
+/// A compiled regex of either engine. Matches are given as byte ranges in the haystack,
+/// fancy-regex runtime errors such as exceeding the backtrack limit end the iteration of
+/// matches, fields and captures. Replacing returns them instead.
+#[derive(Clone)]
+pub enum Regex {
+    Regex(regex::Regex),
+    Fancy(fancy_regex::Regex),
+}
+
+impl Regex {
+    pub fn is_match(&self, haystack: &str) -> bool {
+        match self {
+            Regex::Regex(reg) => reg.is_match(haystack),
+            Regex::Fancy(reg) => reg.is_match(haystack).unwrap_or(false),
+        }
+    }
+
+    pub fn find_iter<'a>(
+        &'a self,
+        haystack: &'a str,
+    ) -> Box<dyn Iterator<Item = Range<usize>> + 'a> {
+        match self {
+            Regex::Regex(reg) => Box::new(reg.find_iter(haystack).map(|m| m.range())),
+            Regex::Fancy(reg) => Box::new(
+                reg.find_iter(haystack)
+                    .map_while(|m| m.ok())
+                    .map(|m| m.range()),
+            ),
+        }
+    }
+
+    /// Every match as the ranges of all its groups, group 0 being the whole match.
+    pub fn captures_iter<'a>(
+        &'a self,
+        haystack: &'a str,
+    ) -> Box<dyn Iterator<Item = Captures> + 'a> {
+        match self {
+            Regex::Regex(reg) => Box::new(
+                reg.captures_iter(haystack)
+                    .map(|caps| caps.iter().map(|m| m.map(|m| m.range())).collect()),
+            ),
+            Regex::Fancy(reg) => Box::new(
+                reg.captures_iter(haystack)
+                    .map_while(|caps| caps.ok())
+                    .map(|caps| caps.iter().map(|m| m.map(|m| m.range())).collect()),
+            ),
+        }
+    }
+
+    pub fn capture_names(&self) -> Vec<Option<String>> {
+        match self {
+            Regex::Regex(reg) => reg
+                .capture_names()
+                .map(|name| name.map(String::from))
+                .collect(),
+            Regex::Fancy(reg) => reg
+                .capture_names()
+                .map(|name| name.map(String::from))
+                .collect(),
+        }
+    }
+
+    /// Index of a group given by number or name, the whole match when empty.
+    pub fn group_index(&self, group: &str) -> Option<usize> {
+        let names = self.capture_names();
+        match group.trim() {
+            "" => Some(0),
+            group => match group.parse::<usize>() {
+                Ok(index) => (index < names.len()).then_some(index),
+                Err(_) => names.iter().position(|name| name.as_deref() == Some(group)),
+            },
+        }
+    }
+
+    /// Split the haystack on the matches, into at most `limit` fields when given.
+    pub fn split<'h>(&self, haystack: &'h str, limit: Option<usize>) -> Vec<&'h str> {
+        match (self, limit) {
+            (Regex::Regex(reg), None) => reg.split(haystack).collect(),
+            (Regex::Regex(reg), Some(limit)) => reg.splitn(haystack, limit).collect(),
+            (Regex::Fancy(reg), None) => reg.split(haystack).map_while(|f| f.ok()).collect(),
+            (Regex::Fancy(reg), Some(limit)) => {
+                reg.splitn(haystack, limit).map_while(|f| f.ok()).collect()
+            }
+        }
+    }
+
+    /// Replace the matches one by one, none when `stop` says so before one of them.
+    pub fn replace_until(
+        &self,
+        haystack: &str,
+        substitution: &str,
+        stop: impl Fn() -> bool,
+    ) -> Result<Option<String>, Error> {
+        let mut replaced = String::with_capacity(haystack.len());
+        let mut last = 0;
+        let mut replace = |whole: Range<usize>, expand: &dyn Fn(&mut String)| {
+            replaced.push_str(&haystack[last..whole.start]);
+            expand(&mut replaced);
+            last = whole.end;
+        };
+        match self {
+            Regex::Regex(reg) => {
+                for caps in reg.captures_iter(haystack) {
+                    if stop() {
+                        return Ok(None);
+                    }
+                    let whole = caps.get(0).map_or(0..0, |m| m.range());
+                    replace(whole, &|dst| caps.expand(substitution, dst));
+                }
+            }
+            Regex::Fancy(reg) => {
+                for caps in reg.captures_iter(haystack) {
+                    if stop() {
+                        return Ok(None);
+                    }
+                    let caps = caps.map_err(|err| Error::Fancy(err, false))?;
+                    let whole = caps.get(0).map_or(0..0, |m| m.range());
+                    replace(whole, &|dst| caps.expand(substitution, dst));
+                }
+            }
+        }
+        replaced.push_str(&haystack[last..]);
+        Ok(Some(replaced))
+    }
+
+    pub fn replace_all(&self, haystack: &str, substitution: &str) -> Result<String, Error> {
+        match self {
+            Regex::Regex(reg) => Ok(reg.replace_all(haystack, substitution).to_string()),
+            Regex::Fancy(reg) => reg
+                .try_replacen(haystack, 0, substitution)
+                .map(|replaced| replaced.to_string())
+                .map_err(|err| Error::Fancy(err, false)),
+        }
+    }
+}
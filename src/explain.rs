@@ -0,0 +1,559 @@
+use std::collections::HashMap;
+
+use fancy_regex::{Assertion as FancyAssertion, Expr, LookAround};
+use ratatui::{
+    layout::Constraint,
+    style::{Color, Style},
+    widgets::{Block, BorderType, Borders, Cell, Row, Table, Widget},
+};
+use regex_syntax::ast::{
+    parse::ParserBuilder, Assertion, AssertionKind, Ast, ClassAsciiKind, ClassPerl, ClassPerlKind,
+    ClassSet, ClassSetBinaryOpKind, ClassSetItem, ClassUnicode, ClassUnicodeKind, GroupKind,
+    Literal, RepetitionKind, RepetitionRange, Span,
+};
+
+use crate::{engine::Engine, flags::Flags};
+
+/// Longest pattern column before the description, deeper nodes are truncated.
+const MAX_PATTERN_WIDTH: usize = 40;
+
+struct Explainer<'a> {
+    pattern: &'a str,
+    rows: Vec<(String, String)>,
+}
+
+impl Explainer<'_> {
+    fn push(&mut self, depth: usize, span: &Span, description: String) {
+        let snippet = &self.pattern[span.start.offset..span.end.offset];
+        self.rows
+            .push((format!("{}{}", "  ".repeat(depth), snippet), description));
+    }
+
+    fn ast(&mut self, ast: &Ast, depth: usize) {
+        match ast {
+            Ast::Empty(span) => self.push(depth, span, "the empty string".to_string()),
+            Ast::Flags(set) => self.push(
+                depth,
+                &set.span,
+                "set flags for the rest of the group".to_string(),
+            ),
+            Ast::Literal(literal) => self.push(depth, &literal.span, literal_description(literal)),
+            Ast::Dot(span) => self.push(depth, span, "any character except \\n".to_string()),
+            Ast::Assertion(assertion) => {
+                self.push(depth, &assertion.span, assertion_description(assertion))
+            }
+            Ast::ClassUnicode(class) => self.push(depth, &class.span, unicode_description(class)),
+            Ast::ClassPerl(class) => self.push(depth, &class.span, perl_description(class)),
+            Ast::ClassBracketed(class) => {
+                let description = if class.negated {
+                    "any character not in the set"
+                } else {
+                    "any character in the set"
+                };
+                self.push(depth, &class.span, description.to_string());
+                self.class_set(&class.kind, depth + 1);
+            }
+            Ast::Repetition(repetition) => {
+                let count = match &repetition.op.kind {
+                    RepetitionKind::ZeroOrOne => "zero or one".to_string(),
+                    RepetitionKind::ZeroOrMore => "zero or more".to_string(),
+                    RepetitionKind::OneOrMore => "one or more".to_string(),
+                    RepetitionKind::Range(RepetitionRange::Exactly(n)) => format!("exactly {}", n),
+                    RepetitionKind::Range(RepetitionRange::AtLeast(n)) => format!("at least {}", n),
+                    RepetitionKind::Range(RepetitionRange::Bounded(n, m)) => {
+                        format!("between {} and {}", n, m)
+                    }
+                };
+                let greed = if repetition.greedy { "" } else { " (lazy)" };
+                self.push(
+                    depth,
+                    &repetition.span,
+                    format!("{} repetitions{} of", count, greed),
+                );
+                self.ast(&repetition.ast, depth + 1);
+            }
+            Ast::Group(group) => {
+                let description = match &group.kind {
+                    GroupKind::CaptureIndex(i) => format!("capture group #{}", i),
+                    GroupKind::CaptureName { name, .. } => {
+                        format!("capture group #{} named `{}`", name.index, name.name)
+                    }
+                    GroupKind::NonCapturing(flags) if flags.items.is_empty() => {
+                        "non-capturing group".to_string()
+                    }
+                    GroupKind::NonCapturing(flags) => format!(
+                        "non-capturing group with flags `{}`",
+                        &self.pattern[flags.span.start.offset..flags.span.end.offset]
+                    ),
+                };
+                self.push(depth, &group.span, format!("{}, containing", description));
+                self.ast(&group.ast, depth + 1);
+            }
+            Ast::Alternation(alternation) => {
+                self.push(
+                    depth,
+                    &alternation.span,
+                    format!("either of {} alternatives", alternation.asts.len()),
+                );
+                for ast in &alternation.asts {
+                    self.ast(ast, depth + 1);
+                }
+            }
+            Ast::Concat(concat) => self.concat(&concat.asts, depth),
+        }
+    }
+
+    /// Runs of plain literals are shown as one string, everything else node by node.
+    fn concat(&mut self, asts: &[Ast], depth: usize) {
+        let mut i = 0;
+        while i < asts.len() {
+            let run = asts[i..]
+                .iter()
+                .take_while(|ast| matches!(ast, Ast::Literal(_)))
+                .count();
+            if run > 1 {
+                let (first, last) = (asts[i].span(), asts[i + run - 1].span());
+                let text = asts[i..i + run]
+                    .iter()
+                    .filter_map(|ast| match ast {
+                        Ast::Literal(literal) => Some(literal.c),
+                        _ => None,
+                    })
+                    .collect::<String>();
+                self.push(
+                    depth,
+                    &Span::new(first.start, last.end),
+                    format!("the literal string `{}`", text.escape_debug()),
+                );
+                i += run;
+            } else {
+                self.ast(&asts[i], depth);
+                i += 1;
+            }
+        }
+    }
+
+    fn class_set(&mut self, set: &ClassSet, depth: usize) {
+        match set {
+            ClassSet::Item(item) => self.class_set_item(item, depth),
+            ClassSet::BinaryOp(op) => {
+                let description = match op.kind {
+                    ClassSetBinaryOpKind::Intersection => "the intersection of",
+                    ClassSetBinaryOpKind::Difference => "the difference of",
+                    ClassSetBinaryOpKind::SymmetricDifference => "the symmetric difference of",
+                };
+                self.push(depth, &op.span, description.to_string());
+                self.class_set(&op.lhs, depth + 1);
+                self.class_set(&op.rhs, depth + 1);
+            }
+        }
+    }
+
+    fn class_set_item(&mut self, item: &ClassSetItem, depth: usize) {
+        match item {
+            ClassSetItem::Empty(span) => self.push(depth, span, "nothing".to_string()),
+            ClassSetItem::Literal(literal) => {
+                self.push(depth, &literal.span, literal_description(literal))
+            }
+            ClassSetItem::Range(range) => self.push(
+                depth,
+                &range.span,
+                format!(
+                    "a character in the range `{}` to `{}`",
+                    range.start.c.escape_debug(),
+                    range.end.c.escape_debug()
+                ),
+            ),
+            ClassSetItem::Ascii(class) => {
+                let description = match (&class.kind, class.negated) {
+                    (ClassAsciiKind::Ascii, false) => "an ASCII character".to_string(),
+                    (ClassAsciiKind::Ascii, true) => "a non-ASCII character".to_string(),
+                    (kind, false) => format!("an ASCII {} character", ascii_name(kind)),
+                    (kind, true) => format!("a character that is not ASCII {}", ascii_name(kind)),
+                };
+                self.push(depth, &class.span, description);
+            }
+            ClassSetItem::Unicode(class) => {
+                self.push(depth, &class.span, unicode_description(class))
+            }
+            ClassSetItem::Perl(class) => self.push(depth, &class.span, perl_description(class)),
+            ClassSetItem::Bracketed(class) => {
+                self.ast(&Ast::ClassBracketed(class.clone()), depth);
+            }
+            ClassSetItem::Union(union) => {
+                for item in &union.items {
+                    self.class_set_item(item, depth);
+                }
+            }
+        }
+    }
+}
+
+/// Explanation of the fancy-regex parse tree, used for patterns the regex crate can't parse.
+/// Parts fancy-regex hands to the regex crate are explained by the regular explainer.
+struct FancyExplainer {
+    rows: Vec<(String, String)>,
+    names: HashMap<usize, String>,
+    group: usize,
+}
+
+impl FancyExplainer {
+    fn push(&mut self, depth: usize, expr: &Expr, description: String) {
+        self.rows.push((
+            format!("{}{}", "  ".repeat(depth), source(expr)),
+            description,
+        ));
+    }
+
+    fn expr(&mut self, expr: &Expr, depth: usize) {
+        match expr {
+            Expr::Empty => self.push(depth, expr, "the empty string".to_string()),
+            Expr::Any { newline: true, .. } => self.push(depth, expr, "any character".to_string()),
+            Expr::Any { .. } => self.push(depth, expr, "any character except \\n".to_string()),
+            Expr::Assertion(assertion) => {
+                let description = match assertion {
+                    FancyAssertion::StartText => "the start of the haystack",
+                    FancyAssertion::EndText => "the end of the haystack",
+                    FancyAssertion::EndTextIgnoreTrailingNewlines { .. } => {
+                        "the end of the haystack, ignoring trailing new lines"
+                    }
+                    FancyAssertion::StartLine { .. } => "the start of a line",
+                    FancyAssertion::EndLine { .. } => "the end of a line",
+                    FancyAssertion::LeftWordBoundary => "the start of a word",
+                    FancyAssertion::RightWordBoundary => "the end of a word",
+                    FancyAssertion::LeftWordHalfBoundary => "the start half of a word boundary",
+                    FancyAssertion::RightWordHalfBoundary => "the end half of a word boundary",
+                    FancyAssertion::WordBoundary => "a word boundary",
+                    FancyAssertion::NotWordBoundary => "not a word boundary",
+                };
+                self.push(depth, expr, description.to_string());
+            }
+            Expr::Literal { val, .. } if val.chars().count() == 1 => self.push(
+                depth,
+                expr,
+                format!("the character `{}`", val.escape_debug()),
+            ),
+            Expr::Literal { val, .. } => self.push(
+                depth,
+                expr,
+                format!("the literal string `{}`", val.escape_debug()),
+            ),
+            Expr::Concat(children) => {
+                // Runs of literals are shown as one string like the regular explainer does.
+                let mut i = 0;
+                while i < children.len() {
+                    let run = children[i..]
+                        .iter()
+                        .take_while(|child| matches!(child, Expr::Literal { .. }))
+                        .count();
+                    if run > 1 {
+                        let literal = Expr::Literal {
+                            val: children[i..i + run]
+                                .iter()
+                                .filter_map(|child| match child {
+                                    Expr::Literal { val, .. } => Some(val.as_str()),
+                                    _ => None,
+                                })
+                                .collect(),
+                            casei: false,
+                        };
+                        self.expr(&literal, depth);
+                        i += run;
+                    } else {
+                        self.expr(&children[i], depth);
+                        i += 1;
+                    }
+                }
+            }
+            Expr::Alt(children) => {
+                self.push(
+                    depth,
+                    expr,
+                    format!("either of {} alternatives", children.len()),
+                );
+                for child in children {
+                    self.expr(child, depth + 1);
+                }
+            }
+            Expr::Group(child) => {
+                self.group += 1;
+                let description = match self.names.get(&self.group) {
+                    Some(name) => format!("capture group #{} named `{}`", self.group, name),
+                    None => format!("capture group #{}", self.group),
+                };
+                self.push(depth, expr, format!("{}, containing", description));
+                self.expr(child, depth + 1);
+            }
+            Expr::LookAround(child, kind) => {
+                let description = match kind {
+                    LookAround::LookAhead => "lookahead, followed by",
+                    LookAround::LookAheadNeg => "negative lookahead, not followed by",
+                    LookAround::LookBehind => "lookbehind, preceded by",
+                    LookAround::LookBehindNeg => "negative lookbehind, not preceded by",
+                };
+                self.push(depth, expr, description.to_string());
+                self.expr(child, depth + 1);
+            }
+            Expr::Repeat {
+                child,
+                lo,
+                hi,
+                greedy,
+            } => {
+                let count = match (*lo, *hi) {
+                    (0, 1) => "zero or one".to_string(),
+                    (0, usize::MAX) => "zero or more".to_string(),
+                    (1, usize::MAX) => "one or more".to_string(),
+                    (lo, usize::MAX) => format!("at least {}", lo),
+                    (lo, hi) if lo == hi => format!("exactly {}", lo),
+                    (lo, hi) => format!("between {} and {}", lo, hi),
+                };
+                let greed = if *greedy { "" } else { " (lazy)" };
+                self.push(depth, expr, format!("{} repetitions{} of", count, greed));
+                self.expr(child, depth + 1);
+            }
+            Expr::Delegate { inner, .. } => match ParserBuilder::new().build().parse(inner) {
+                Ok(ast) => {
+                    let mut explainer = Explainer {
+                        pattern: inner,
+                        rows: vec![],
+                    };
+                    explainer.ast(&ast, depth);
+                    self.rows.append(&mut explainer.rows);
+                }
+                Err(_) => self.push(depth, expr, "handled by the regex crate".to_string()),
+            },
+            Expr::Backref { group, .. } => match self.names.get(group) {
+                Some(name) => self.push(
+                    depth,
+                    expr,
+                    format!("backreference to group #{} named `{}`", group, name),
+                ),
+                None => self.push(depth, expr, format!("backreference to group #{}", group)),
+            },
+            Expr::AtomicGroup(child) => {
+                self.push(
+                    depth,
+                    expr,
+                    "atomic group, without backtracking into".to_string(),
+                );
+                self.expr(child, depth + 1);
+            }
+            Expr::KeepOut => self.push(depth, expr, "reset the start of the match".to_string()),
+            Expr::ContinueFromPreviousMatchEnd => {
+                self.push(depth, expr, "the end of the previous match".to_string())
+            }
+            _ => self.push(depth, expr, "a fancy-regex construct".to_string()),
+        }
+    }
+}
+
+/// Pattern text for a fancy-regex node, `to_str` of fancy-regex panics on the nodes
+/// the regex crate can't handle.
+fn source(expr: &Expr) -> String {
+    match expr {
+        Expr::Empty => String::new(),
+        Expr::Any { .. } => ".".to_string(),
+        Expr::Assertion(assertion) => match assertion {
+            FancyAssertion::StartText | FancyAssertion::StartLine { .. } => "^",
+            FancyAssertion::EndText | FancyAssertion::EndLine { .. } => "$",
+            FancyAssertion::EndTextIgnoreTrailingNewlines { .. } => "\\Z",
+            FancyAssertion::LeftWordBoundary => "\\<",
+            FancyAssertion::RightWordBoundary => "\\>",
+            FancyAssertion::LeftWordHalfBoundary => "\\b{start-half}",
+            FancyAssertion::RightWordHalfBoundary => "\\b{end-half}",
+            FancyAssertion::WordBoundary => "\\b",
+            FancyAssertion::NotWordBoundary => "\\B",
+        }
+        .to_string(),
+        Expr::Literal { val, .. } => fancy_regex::escape(val).to_string(),
+        Expr::Concat(children) => children.iter().map(source).collect(),
+        Expr::Alt(children) => children.iter().map(source).collect::<Vec<_>>().join("|"),
+        Expr::Group(child) => format!("({})", source(child)),
+        Expr::LookAround(child, kind) => {
+            let prefix = match kind {
+                LookAround::LookAhead => "(?=",
+                LookAround::LookAheadNeg => "(?!",
+                LookAround::LookBehind => "(?<=",
+                LookAround::LookBehindNeg => "(?<!",
+            };
+            format!("{}{})", prefix, source(child))
+        }
+        Expr::Repeat {
+            child,
+            lo,
+            hi,
+            greedy,
+        } => {
+            let child = match child.as_ref() {
+                Expr::Concat(_) | Expr::Alt(_) => format!("(?:{})", source(child)),
+                child => source(child),
+            };
+            let op = match (*lo, *hi) {
+                (0, 1) => "?".to_string(),
+                (0, usize::MAX) => "*".to_string(),
+                (1, usize::MAX) => "+".to_string(),
+                (lo, usize::MAX) => format!("{{{},}}", lo),
+                (lo, hi) if lo == hi => format!("{{{}}}", lo),
+                (lo, hi) => format!("{{{},{}}}", lo, hi),
+            };
+            let greed = if *greedy { "" } else { "?" };
+            format!("{}{}{}", child, op, greed)
+        }
+        Expr::Delegate { inner, .. } => inner.clone(),
+        Expr::Backref { group, .. } => format!("\\{}", group),
+        Expr::AtomicGroup(child) => format!("(?>{})", source(child)),
+        Expr::KeepOut => "\\K".to_string(),
+        Expr::ContinueFromPreviousMatchEnd => "\\G".to_string(),
+        _ => "…".to_string(),
+    }
+}
+
+fn literal_description(literal: &Literal) -> String {
+    match literal.c {
+        '\n' => "a new line".to_string(),
+        '\r' => "a carriage return".to_string(),
+        '\t' => "a tab".to_string(),
+        ' ' => "a space".to_string(),
+        c => format!("the character `{}`", c.escape_debug()),
+    }
+}
+
+fn assertion_description(assertion: &Assertion) -> String {
+    match assertion.kind {
+        AssertionKind::StartLine => "the start of the haystack (or line with flag m)",
+        AssertionKind::EndLine => "the end of the haystack (or line with flag m)",
+        AssertionKind::StartText => "the start of the haystack",
+        AssertionKind::EndText => "the end of the haystack",
+        AssertionKind::WordBoundary => "a word boundary",
+        AssertionKind::NotWordBoundary => "not a word boundary",
+        AssertionKind::WordBoundaryStart | AssertionKind::WordBoundaryStartAngle => {
+            "the start of a word"
+        }
+        AssertionKind::WordBoundaryEnd | AssertionKind::WordBoundaryEndAngle => "the end of a word",
+        AssertionKind::WordBoundaryStartHalf => "the start half of a word boundary",
+        AssertionKind::WordBoundaryEndHalf => "the end half of a word boundary",
+    }
+    .to_string()
+}
+
+fn perl_description(class: &ClassPerl) -> String {
+    match (&class.kind, class.negated) {
+        (ClassPerlKind::Digit, false) => "a digit",
+        (ClassPerlKind::Digit, true) => "a character that is not a digit",
+        (ClassPerlKind::Space, false) => "a whitespace character",
+        (ClassPerlKind::Space, true) => "a character that is not whitespace",
+        (ClassPerlKind::Word, false) => "a word character",
+        (ClassPerlKind::Word, true) => "a character that is not a word character",
+    }
+    .to_string()
+}
+
+fn unicode_description(class: &ClassUnicode) -> String {
+    let name = match &class.kind {
+        ClassUnicodeKind::OneLetter(c) => c.to_string(),
+        ClassUnicodeKind::Named(name) => name.clone(),
+        ClassUnicodeKind::NamedValue { name, value, .. } => format!("{}={}", name, value),
+    };
+    if class.is_negated() {
+        format!("a character not in the unicode class `{}`", name)
+    } else {
+        format!("a character in the unicode class `{}`", name)
+    }
+}
+
+fn ascii_name(kind: &ClassAsciiKind) -> &'static str {
+    match kind {
+        ClassAsciiKind::Alnum => "alphanumeric",
+        ClassAsciiKind::Alpha => "alphabetic",
+        ClassAsciiKind::Ascii => "ascii",
+        ClassAsciiKind::Blank => "blank",
+        ClassAsciiKind::Cntrl => "control",
+        ClassAsciiKind::Digit => "digit",
+        ClassAsciiKind::Graph => "graphical",
+        ClassAsciiKind::Lower => "lowercase",
+        ClassAsciiKind::Print => "printable",
+        ClassAsciiKind::Punct => "punctuation",
+        ClassAsciiKind::Space => "whitespace",
+        ClassAsciiKind::Upper => "uppercase",
+        ClassAsciiKind::Word => "word",
+        ClassAsciiKind::Xdigit => "hex digit",
+    }
+}
+
+pub fn explain(pattern: &str, flags: &Flags, engine: Engine) -> impl Widget {
+    let block = Block::new()
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Gray))
+        .borders(Borders::ALL)
+        .title("Explain");
+    let parsed = ParserBuilder::new()
+        .ignore_whitespace(flags.ignore_whitespace)
+        .build()
+        .parse(pattern);
+    let rows = match (parsed, engine) {
+        (Ok(ast), _) => {
+            let mut explainer = Explainer {
+                pattern,
+                rows: vec![],
+            };
+            explainer.ast(&ast, 0);
+            Ok(explainer.rows)
+        }
+        (Err(_), Engine::Fancy) => {
+            let prefix = if flags.ignore_whitespace { "(?x)" } else { "" };
+            match Expr::parse_tree(&format!("{}{}", prefix, pattern)) {
+                Ok(tree) => {
+                    let mut explainer = FancyExplainer {
+                        rows: vec![],
+                        names: tree
+                            .named_groups
+                            .iter()
+                            .map(|(name, i)| (*i, name.clone()))
+                            .collect(),
+                        group: 0,
+                    };
+                    explainer.expr(&tree.expr, 0);
+                    Ok(explainer.rows)
+                }
+                Err(err) => Err(err.to_string()),
+            }
+        }
+        (Err(err), Engine::Regex) => Err(err.to_string()),
+    };
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(err) => {
+            let height = err.lines().count() as u16;
+            return Table::new(
+                vec![Row::new(vec![err]).height(height)],
+                vec![Constraint::Fill(1)],
+            )
+            .style(Style::default().fg(Color::LightRed))
+            .block(block);
+        }
+    };
+
+    let width = rows
+        .iter()
+        .map(|(snippet, _)| snippet.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(MAX_PATTERN_WIDTH);
+    let rows = rows
+        .into_iter()
+        .map(|(snippet, description)| {
+            Row::new(vec![
+                Cell::new(snippet).style(Style::default().fg(Color::Cyan)),
+                Cell::new(description),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    Table::new(
+        rows,
+        vec![Constraint::Length(width as u16), Constraint::Fill(1)],
+    )
+    .column_spacing(2)
+    .block(block)
+}
@@ -0,0 +1,498 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::{
+    command::{perl, ripgrep, sed, shell_quoted},
+    engine::Engine,
+    flags::{Flags, FLAGS},
+};
+
+#[derive(Clone, Copy)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    Go,
+    Json,
+    Shell,
+    Sed,
+    Perl,
+    Ripgrep,
+}
+
+/// Languages in the order they are shown in the export menu.
+pub const LANGUAGES: [Language; 9] = [
+    Language::Rust,
+    Language::Python,
+    Language::JavaScript,
+    Language::Go,
+    Language::Json,
+    Language::Shell,
+    Language::Sed,
+    Language::Perl,
+    Language::Ripgrep,
+];
+
+impl Language {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Rust => "Rust",
+            Language::Python => "Python",
+            Language::JavaScript => "JavaScript",
+            Language::Go => "Go",
+            Language::Json => "JSON",
+            Language::Shell => "Shell",
+            Language::Sed => "sed",
+            Language::Perl => "perl",
+            Language::Ripgrep => "rg",
+        }
+    }
+
+    /// Flag characters the language can express, the others are listed in a comment.
+    fn supported_flags(&self) -> &'static str {
+        match self {
+            Language::Rust | Language::Json | Language::Shell | Language::Ripgrep => "imsxUuR",
+            Language::Python => "imsxu",
+            Language::Sed => "im",
+            Language::Perl => "imsx",
+            Language::JavaScript => "ims",
+            Language::Go => "imsU",
+        }
+    }
+
+    fn comment(&self) -> Option<&'static str> {
+        match self {
+            Language::Rust | Language::JavaScript | Language::Go => Some("//"),
+            Language::Python
+            | Language::Shell
+            | Language::Sed
+            | Language::Perl
+            | Language::Ripgrep => Some("#"),
+            Language::Json => None,
+        }
+    }
+
+    /// The regex as code for the language, with the substitution applied to `text` when
+    /// there is one. Commands run the substitution on their input, or search without one.
+    pub fn export(
+        &self,
+        regex: &str,
+        flags: &Flags,
+        engine: Engine,
+        substitution: Option<&str>,
+    ) -> String {
+        let mut lines = Vec::new();
+        if let Some(comment) = self.comment() {
+            let unsupported = FLAGS
+                .iter()
+                .filter(|(flag, _)| {
+                    flags.is_set(*flag) != Flags::default().is_set(*flag)
+                        && !self.supported_flags().contains(*flag)
+                })
+                .map(|(flag, name)| format!("{} {}", flag, name))
+                .collect::<Vec<_>>();
+            if !unsupported.is_empty() {
+                lines.push(format!(
+                    "{} not supported in {}: {}",
+                    comment,
+                    self.name(),
+                    unsupported.join(", ")
+                ));
+            }
+            if matches!(self, Language::Go) && Engine::Regex.build(regex, flags).is_err() {
+                lines.push(format!(
+                    "{} Go's regexp has no look-around or backreferences",
+                    comment
+                ));
+            }
+        }
+        let command = match self {
+            Language::Sed => Some(sed(regex, flags, substitution)),
+            Language::Perl => Some(perl(regex, flags, substitution)),
+            Language::Ripgrep => Some(ripgrep(regex, flags, engine, substitution)),
+            _ => None,
+        };
+        if let Some((command, warnings)) = command {
+            for warning in warnings {
+                lines.push(format!("# {}", warning));
+            }
+            lines.push(command);
+            return lines.join("\n");
+        }
+        lines.push(match self {
+            Language::Rust => rust(regex, flags, engine, substitution),
+            Language::Python => python(regex, flags, substitution),
+            Language::JavaScript => javascript(regex, flags, substitution),
+            Language::Go => go(regex, flags, substitution),
+            Language::Json => match substitution {
+                Some(sub) => format!(
+                    "{{\"regex\": {}, \"substitution\": {}}}",
+                    quoted(&format!("{}{}", flags.inline(), regex)),
+                    quoted(sub)
+                ),
+                None => quoted(&format!("{}{}", flags.inline(), regex)),
+            },
+            Language::Shell => shell_quoted(&format!("{}{}", flags.inline(), regex)),
+            Language::Sed | Language::Perl | Language::Ripgrep => {
+                unreachable!("commands are exported above")
+            }
+        });
+        lines.join("\n")
+    }
+}
+
+fn rust(regex: &str, flags: &Flags, engine: Engine, substitution: Option<&str>) -> String {
+    let path = match engine {
+        Engine::Regex => "Regex",
+        Engine::Fancy => "fancy_regex::Regex",
+    };
+    let new = format!(
+        "{}::new({}).unwrap()",
+        path,
+        rust_raw(&format!("{}{}", flags.inline(), regex))
+    );
+    match substitution {
+        Some(sub) => format!(
+            "let re = {};\nlet result = re.replace_all(text, {});",
+            new,
+            rust_raw(sub)
+        ),
+        None => new,
+    }
+}
+
+fn python(regex: &str, flags: &Flags, substitution: Option<&str>) -> String {
+    let mut names = Vec::new();
+    for (set, name) in [
+        (flags.case_insensitive, "re.IGNORECASE"),
+        (flags.multi_line, "re.MULTILINE"),
+        (flags.dot_matches_new_line, "re.DOTALL"),
+        (flags.ignore_whitespace, "re.VERBOSE"),
+        (!flags.unicode, "re.ASCII"),
+    ] {
+        if set {
+            names.push(name);
+        }
+    }
+    let regex = python_raw(&named_groups(regex, "(?P<"));
+    let compile = match names.is_empty() {
+        true => format!("re.compile({})", regex),
+        false => format!("re.compile({}, {})", regex, names.join(" | ")),
+    };
+    match substitution {
+        Some(sub) => {
+            let sub = parse_replacement(sub)
+                .into_iter()
+                .map(|part| match part {
+                    Part::Literal(s) => s.replace('\\', r"\\"),
+                    Part::Group(g) => format!(r"\g<{}>", g),
+                })
+                .collect::<String>();
+            format!(
+                "pattern = {}\nresult = pattern.sub({}, text)",
+                compile,
+                python_raw(&sub)
+            )
+        }
+        None => compile,
+    }
+}
+
+fn javascript(regex: &str, flags: &Flags, substitution: Option<&str>) -> String {
+    let mut letters = String::new();
+    if substitution.is_some() {
+        letters.push('g');
+    }
+    for flag in ['i', 'm', 's'] {
+        if flags.is_set(flag) {
+            letters.push(flag);
+        }
+    }
+    let mut literal = String::from("/");
+    let regex = named_groups(regex, "(?<");
+    let mut chars = regex.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                literal.push(c);
+                literal.extend(chars.next());
+            }
+            '/' => literal.push_str(r"\/"),
+            c => literal.push(c),
+        }
+    }
+    literal = format!("{}/{}", literal, letters);
+    match substitution {
+        Some(sub) => {
+            let parts = parse_replacement(sub);
+            let mut replacement = String::new();
+            for (i, part) in parts.iter().enumerate() {
+                match part {
+                    Part::Literal(s) => replacement.push_str(&s.replace('$', "$$")),
+                    Part::Group(g) if g == "0" => replacement.push_str("$&"),
+                    Part::Group(g) if g.chars().all(|c| c.is_ascii_digit()) => {
+                        // `$12` would be read as group 12, the two digit form ends the number.
+                        let digit_follows = matches!(
+                            parts.get(i + 1),
+                            Some(Part::Literal(s)) if s.starts_with(|c: char| c.is_ascii_digit())
+                        );
+                        match digit_follows && g.len() == 1 {
+                            true => replacement.push_str(&format!("$0{}", g)),
+                            false => replacement.push_str(&format!("${}", g)),
+                        }
+                    }
+                    Part::Group(g) => replacement.push_str(&format!("$<{}>", g)),
+                }
+            }
+            format!(
+                "const result = text.replace({}, {});",
+                literal,
+                quoted(&replacement)
+            )
+        }
+        None => literal,
+    }
+}
+
+fn go(regex: &str, flags: &Flags, substitution: Option<&str>) -> String {
+    let letters = "imsU"
+        .chars()
+        .filter(|flag| flags.is_set(*flag))
+        .collect::<String>();
+    let regex = match letters.is_empty() {
+        true => named_groups(regex, "(?P<"),
+        false => format!("(?{}){}", letters, named_groups(regex, "(?P<")),
+    };
+    let compile = format!("regexp.MustCompile({})", go_raw(&regex));
+    match substitution {
+        Some(sub) => format!(
+            "re := {}\nresult := re.ReplaceAllString(text, {})",
+            compile,
+            go_raw(sub)
+        ),
+        None => compile,
+    }
+}
+
+/// A raw string with as many `#` as needed to hold the text.
+fn rust_raw(s: &str) -> String {
+    let mut hashes = String::new();
+    while s.contains(&format!("\"{}", hashes)) {
+        hashes.push('#');
+    }
+    format!("r{}\"{}\"{}", hashes, s, hashes)
+}
+
+/// A raw string when the text allows one, raw strings can not hold their quote or end in a
+/// backslash.
+fn python_raw(s: &str) -> String {
+    if s.ends_with('\\') {
+        quoted(s)
+    } else if !s.contains('"') {
+        format!("r\"{}\"", s)
+    } else if !s.contains('\'') {
+        format!("r'{}'", s)
+    } else {
+        quoted(s)
+    }
+}
+
+fn go_raw(s: &str) -> String {
+    match s.contains('`') {
+        true => quoted(s),
+        false => format!("`{}`", s),
+    }
+}
+
+/// A double quoted string with JSON escapes, also valid in Python, JavaScript and Go.
+fn quoted(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Rewrite named groups, `(?<name>` and `(?P<name>`, with the given opening.
+fn named_groups(regex: &str, open: &str) -> String {
+    let mut out = String::new();
+    let mut rest = regex;
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            let len = rest[1..].chars().next().map_or(0, char::len_utf8);
+            out.push_str(&rest[..1 + len]);
+            rest = &rest[1 + len..];
+        } else if let Some(after) = rest.strip_prefix("(?P<").or_else(|| {
+            rest.strip_prefix("(?<")
+                .filter(|after| !after.starts_with(['=', '!']))
+        }) {
+            out.push_str(open);
+            rest = after;
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+pub enum Part {
+    Literal(String),
+    /// A group number or name.
+    Group(String),
+}
+
+/// Split a substitution in the regex crate syntax, `$1`, `${name}` and `$$`, into parts.
+pub fn parse_replacement(sub: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut rest = sub;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        if c != '$' {
+            literal.push(c);
+            continue;
+        }
+        let group = if let Some(after) = rest.strip_prefix('$') {
+            rest = after;
+            None
+        } else if let Some((name, after)) = rest
+            .strip_prefix('{')
+            .and_then(|braced| braced.split_once('}'))
+        {
+            rest = after;
+            Some(name.to_string())
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let name = &rest[..end];
+            rest = &rest[end..];
+            (!name.is_empty()).then(|| name.to_string())
+        };
+        match group {
+            Some(group) => {
+                if !literal.is_empty() {
+                    parts.push(Part::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(Part::Group(group));
+            }
+            None => literal.push('$'),
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+    parts
+}
+
+/// The alt+e overlay to export the regex as code.
+pub struct ExportMenu {
+    regexes: Vec<String>,
+    flags: Flags,
+    engine: Engine,
+    substitution: Option<String>,
+    selected: usize,
+}
+
+impl ExportMenu {
+    /// Every regex is exported on its own, the substitution is applied when there is one.
+    pub fn new(
+        regexes: Vec<String>,
+        flags: Flags,
+        engine: Engine,
+        substitution: Option<String>,
+    ) -> Self {
+        Self {
+            regexes,
+            flags,
+            engine,
+            substitution,
+            selected: 0,
+        }
+    }
+
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self) {
+        self.selected = (self.selected + 1).min(LANGUAGES.len() - 1);
+    }
+
+    pub fn language(&self) -> Language {
+        LANGUAGES[self.selected]
+    }
+
+    /// The export in the selected language.
+    pub fn current(&self) -> String {
+        let language = self.language();
+        self.regexes
+            .iter()
+            .map(|regex| {
+                language.export(
+                    regex,
+                    &self.flags,
+                    self.engine,
+                    self.substitution.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn widget(&self) -> impl Widget + '_ {
+        struct Menu<'b>(&'b ExportMenu);
+
+        impl Widget for Menu<'_> {
+            fn render(self, area: Rect, buf: &mut Buffer) {
+                let Menu(menu) = self;
+                Clear.render(area, buf);
+                let block = Block::new()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::Cyan))
+                    .borders(Borders::ALL)
+                    .title("Export (enter export, c copy, esc cancel)");
+                let inner = block.inner(area);
+                block.render(area, buf);
+
+                let layout = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(vec![Constraint::Length(12), Constraint::Fill(1)])
+                    .split(inner);
+                let lines = LANGUAGES
+                    .iter()
+                    .enumerate()
+                    .map(|(i, language)| {
+                        let line = Line::raw(language.name());
+                        match i == menu.selected {
+                            true => line.style(Style::default().add_modifier(Modifier::REVERSED)),
+                            false => line,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                Paragraph::new(lines).render(layout[0], buf);
+                Paragraph::new(menu.current())
+                    .style(Style::default().fg(Color::LightGreen))
+                    .wrap(Wrap { trim: false })
+                    .render(layout[1], buf);
+            }
+        }
+
+        Menu(self)
+    }
+}
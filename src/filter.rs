@@ -0,0 +1,215 @@
+use std::{fmt, str::FromStr};
+
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Borders, Paragraph, Widget},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Regex;
+
+/// Lines of context before and after every selected line, written as `2` or `1,3`.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Context {
+    pub before: usize,
+    pub after: usize,
+}
+
+impl FromStr for Context {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid context '{}'", s))
+        };
+        match s.split_once(',') {
+            Some((before, after)) => Ok(Context {
+                before: parse(before)?,
+                after: parse(after)?,
+            }),
+            None if s.trim().is_empty() => Ok(Context::default()),
+            None => {
+                let n = parse(s)?;
+                Ok(Context {
+                    before: n,
+                    after: n,
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Context {
+                before: 0,
+                after: 0,
+            } => Ok(()),
+            Context { before, after } if before == after => write!(f, "{}", before),
+            Context { before, after } => write!(f, "{},{}", before, after),
+        }
+    }
+}
+
+impl TryFrom<String> for Context {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Context> for String {
+    fn from(context: Context) -> Self {
+        context.to_string()
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FilterOptions {
+    pub invert: bool,
+    pub line_numbers: bool,
+    pub context: Context,
+}
+
+pub enum FilteredLine<'a> {
+    Line {
+        number: usize,
+        text: &'a str,
+        selected: bool,
+    },
+    /// Between groups of lines that are not adjacent, like `--` in grep.
+    Separator,
+}
+
+/// Whether every line of the body matches the regex, or doesn't when inverted.
+pub fn selected_lines<'a>(
+    body: &'a str,
+    reg: Option<&'a Regex>,
+    invert: bool,
+) -> impl Iterator<Item = bool> + 'a {
+    body.lines().map(move |line| match reg {
+        Some(reg) => reg.is_match(line) != invert,
+        None => true,
+    })
+}
+
+/// Lines that match the regex (or don't, when inverted) with their context lines.
+pub fn filter_lines<'a>(
+    body: &'a str,
+    reg: Option<&Regex>,
+    options: FilterOptions,
+) -> Vec<FilteredLine<'a>> {
+    let selected = selected_lines(body, reg, options.invert).collect::<Vec<_>>();
+    with_context(body, &selected, options)
+}
+
+/// The selected lines, one flag per line of the body, with their context lines.
+pub fn with_context<'a>(
+    body: &'a str,
+    selected: &[bool],
+    options: FilterOptions,
+) -> Vec<FilteredLine<'a>> {
+    let lines = body.lines().collect::<Vec<_>>();
+
+    let mut visible = vec![false; lines.len()];
+    for (i, _) in selected.iter().enumerate().filter(|(_, s)| **s) {
+        let start = i.saturating_sub(options.context.before);
+        let end = (i + options.context.after).min(lines.len() - 1);
+        visible[start..=end].iter_mut().for_each(|v| *v = true);
+    }
+
+    let mut filtered = vec![];
+    let mut last = None;
+    for (i, line) in lines.iter().enumerate().filter(|(i, _)| visible[*i]) {
+        if last.is_some_and(|last| last + 1 != i) && options.context != Context::default() {
+            filtered.push(FilteredLine::Separator);
+        }
+        filtered.push(FilteredLine::Line {
+            number: i + 1,
+            text: line,
+            selected: selected[i],
+        });
+        last = Some(i);
+    }
+    filtered
+}
+
+/// The lines of the body filtered by the regex as grep would print them.
+pub fn filter(body: &str, reg: Option<&Regex>, options: FilterOptions) -> String {
+    printed(filter_lines(body, reg, options), options)
+}
+
+/// The filtered lines as grep would print them, `:` after the number of a selected line
+/// and `-` after the number of a context line.
+pub fn printed(lines: Vec<FilteredLine>, options: FilterOptions) -> String {
+    lines
+        .into_iter()
+        .map(|line| match line {
+            FilteredLine::Line {
+                number,
+                text,
+                selected,
+            } if options.line_numbers => {
+                format!("{}{}{}", number, if selected { ':' } else { '-' }, text)
+            }
+            FilteredLine::Line { text, .. } => text.to_string(),
+            FilteredLine::Separator => "--".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn filtered(body: &str, selected: &[bool], options: FilterOptions) -> impl Widget {
+    let lines = with_context(body, selected, options);
+    let count = lines
+        .iter()
+        .filter(|line| matches!(line, FilteredLine::Line { selected: true, .. }))
+        .count();
+    let mut text = Text::default();
+    for line in lines {
+        text.push_line(match line {
+            FilteredLine::Line {
+                number,
+                text,
+                selected,
+            } => {
+                let style = if selected {
+                    Style::default()
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                let mut spans = vec![];
+                if options.line_numbers {
+                    spans.push(Span::styled(
+                        format!("{}{}", number, if selected { ':' } else { '-' }),
+                        Style::default().fg(Color::Green),
+                    ));
+                }
+                spans.push(Span::styled(text.to_string(), style));
+                Line::from(spans)
+            }
+            FilteredLine::Separator => Line::styled("--", Style::default().fg(Color::Cyan)),
+        });
+    }
+
+    let title = format!(
+        "Filtered, {} {}line{}",
+        count,
+        if options.invert { "non-matching " } else { "" },
+        if count == 1 { "" } else { "s" }
+    );
+    Paragraph::new(text).block(
+        Block::new()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Gray))
+            .borders(Borders::ALL)
+            .title(title),
+    )
+}
@@ -0,0 +1,180 @@
+use std::{fmt, str::FromStr};
+
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use regex_syntax::{Parser, ParserBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Flag characters in the order they are shown, with the name used in the flags bar.
+pub const FLAGS: [(char, &str); 7] = [
+    ('i', "case-insensitive"),
+    ('m', "multi-line"),
+    ('s', "dot matches \\n"),
+    ('x', "ignore whitespace"),
+    ('U', "swap greed"),
+    ('u', "unicode"),
+    ('R', "crlf"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Flags {
+    pub case_insensitive: bool,
+    pub multi_line: bool,
+    pub dot_matches_new_line: bool,
+    pub ignore_whitespace: bool,
+    pub swap_greed: bool,
+    pub unicode: bool,
+    pub crlf: bool,
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Self {
+            case_insensitive: false,
+            multi_line: false,
+            dot_matches_new_line: false,
+            ignore_whitespace: false,
+            swap_greed: false,
+            unicode: true,
+            crlf: false,
+        }
+    }
+}
+
+impl Flags {
+    fn flag_mut(&mut self, flag: char) -> Option<&mut bool> {
+        match flag {
+            'i' => Some(&mut self.case_insensitive),
+            'm' => Some(&mut self.multi_line),
+            's' => Some(&mut self.dot_matches_new_line),
+            'x' => Some(&mut self.ignore_whitespace),
+            'U' => Some(&mut self.swap_greed),
+            'u' => Some(&mut self.unicode),
+            'R' => Some(&mut self.crlf),
+            _ => None,
+        }
+    }
+
+    pub fn is_set(&self, flag: char) -> bool {
+        match flag {
+            'i' => self.case_insensitive,
+            'm' => self.multi_line,
+            's' => self.dot_matches_new_line,
+            'x' => self.ignore_whitespace,
+            'U' => self.swap_greed,
+            'u' => self.unicode,
+            'R' => self.crlf,
+            _ => false,
+        }
+    }
+
+    /// Toggle a flag by its character, returns false if the character is not a flag.
+    pub fn toggle(&mut self, flag: char) -> bool {
+        match self.flag_mut(flag) {
+            Some(f) => {
+                *f = !*f;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Flags that differ from the default in inline syntax, e.g. `im-u`.
+    pub fn letters(&self) -> String {
+        let default = Flags::default();
+        let (mut enabled, mut disabled) = (String::new(), String::new());
+        for (flag, _) in FLAGS {
+            match (self.is_set(flag), default.is_set(flag)) {
+                (true, false) => enabled.push(flag),
+                (false, true) => disabled.push(flag),
+                _ => {}
+            }
+        }
+        if disabled.is_empty() {
+            enabled
+        } else {
+            format!("{}-{}", enabled, disabled)
+        }
+    }
+
+    /// Prefix that sets the flags inline, e.g. `(?im)`, or nothing when all flags are default.
+    pub fn inline(&self) -> String {
+        let letters = self.letters();
+        if letters.is_empty() {
+            letters
+        } else {
+            format!("(?{})", letters)
+        }
+    }
+
+    pub fn bar(&self) -> Line<'static> {
+        let mut spans = vec![Span::raw(" alt+")];
+        for (flag, name) in FLAGS {
+            let style = if self.is_set(flag) {
+                Style::default().fg(Color::Black).bg(Color::Green)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            spans.push(Span::styled(format!(" {} {} ", flag, name), style));
+            spans.push(Span::raw(" "));
+        }
+        Line::from(spans)
+    }
+
+    /// Parser for the same syntax and flags, used for error positions.
+    pub fn parser(&self) -> Parser {
+        ParserBuilder::new()
+            .case_insensitive(self.case_insensitive)
+            .multi_line(self.multi_line)
+            .dot_matches_new_line(self.dot_matches_new_line)
+            .ignore_whitespace(self.ignore_whitespace)
+            .swap_greed(self.swap_greed)
+            .unicode(self.unicode)
+            .crlf(self.crlf)
+            .build()
+    }
+}
+
+impl fmt::Display for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.letters())
+    }
+}
+
+impl FromStr for Flags {
+    type Err = String;
+
+    /// Parse flags in inline syntax, e.g. `im` or `i-u`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut flags = Flags::default();
+        let mut enable = true;
+        for c in s.chars() {
+            if c == '-' {
+                enable = false;
+                continue;
+            }
+            match flags.flag_mut(c) {
+                Some(f) => *f = enable,
+                None => return Err(format!("unknown flag '{}'", c)),
+            }
+        }
+        Ok(flags)
+    }
+}
+
+impl TryFrom<String> for Flags {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Flags> for String {
+    fn from(flags: Flags) -> Self {
+        flags.letters()
+    }
+}
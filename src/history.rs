@@ -0,0 +1,162 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::Widget,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{engine::Engine, flags::Flags, picker::Picker};
+
+/// Entries kept in the history file, older ones are dropped.
+const MAX_ENTRIES: usize = 500;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub regex: String,
+    #[serde(default)]
+    pub flags: Flags,
+    #[serde(default)]
+    pub engine: Engine,
+    #[serde(default)]
+    pub substitution: String,
+    /// Seconds since the unix epoch when the pattern was last exported or saved.
+    #[serde(default)]
+    pub time: u64,
+}
+
+impl Entry {
+    pub fn new(regex: String, flags: Flags, engine: Engine, substitution: String) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self {
+            regex,
+            flags,
+            engine,
+            substitution,
+            time,
+        }
+    }
+
+    /// How long ago the entry was recorded, e.g. `5m` or `3d`.
+    fn age(&self) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        match now.saturating_sub(self.time) {
+            s if s < 60 => format!("{}s", s),
+            s if s < 60 * 60 => format!("{}m", s / 60),
+            s if s < 60 * 60 * 24 => format!("{}h", s / (60 * 60)),
+            s => format!("{}d", s / (60 * 60 * 24)),
+        }
+    }
+
+    /// Same pattern, regardless of when it was recorded.
+    pub fn same(&self, other: &Entry) -> bool {
+        self.regex == other.regex
+            && self.flags == other.flags
+            && self.engine == other.engine
+            && self.substitution == other.substitution
+    }
+}
+
+/// Previously exported or saved patterns, oldest first, stored as TOML in the data directory.
+#[derive(Default, Serialize, Deserialize)]
+pub struct History {
+    #[serde(default, rename = "entry")]
+    entries: Vec<Entry>,
+}
+
+impl History {
+    fn path() -> Option<PathBuf> {
+        Some(dirs::data_dir()?.join("regect").join("history.toml"))
+    }
+
+    /// The history, empty when there is none yet or it can not be read.
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|toml| toml::from_str(&toml).ok())
+            .unwrap_or_default()
+    }
+
+    /// Add the entry as the newest, dropping an older copy of it, and save the history.
+    pub fn record(&mut self, entry: Entry) -> io::Result<()> {
+        if entry.regex.is_empty() {
+            return Ok(());
+        }
+        self.entries.retain(|e| !e.same(&entry));
+        self.entries.push(entry);
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+
+        let path =
+            Self::path().ok_or(io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let toml =
+            toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(path, toml)
+    }
+
+    /// The entry `back` steps from the newest one.
+    pub fn get(&self, back: usize) -> Option<&Entry> {
+        self.entries.iter().rev().nth(back)
+    }
+
+    pub fn last(&self) -> Option<&Entry> {
+        self.get(0)
+    }
+
+    /// Entries whose regex or substitution fuzzily match the query, newest first.
+    pub fn search(&self, query: &str) -> Vec<&Entry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|e| fuzzy_match(query, &e.regex) || fuzzy_match(query, &e.substitution))
+            .collect()
+    }
+
+    /// The entries matching the query of `search`, with their substitution.
+    pub fn picker<'b>(&'b self, search: &'b Picker) -> impl Widget + 'b {
+        search.widget(self.search(search.current_query()), |entry| {
+            let mut spans = vec![
+                Span::styled(
+                    format!("{:>4} ", entry.age()),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    format!("{}{}", entry.flags.inline(), entry.regex),
+                    Style::default().fg(Color::LightGreen),
+                ),
+            ];
+            if !entry.substitution.is_empty() {
+                spans.push(Span::raw(" → "));
+                spans.push(Span::raw(entry.substitution.clone()));
+            }
+            Line::from(spans)
+        })
+    }
+}
+
+/// Whether all chars of the query appear in the text in order, ignoring case.
+fn fuzzy_match(query: &str, text: &str) -> bool {
+    let mut chars = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|q| chars.any(|c| c == q))
+}
+
+/// The ^r overlay to search the history.
+pub fn history_search<'a>() -> Picker<'a> {
+    Picker::new("History (enter recall, esc cancel)", "Search the history")
+}
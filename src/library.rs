@@ -0,0 +1,180 @@
+use std::fs;
+
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::Widget,
+};
+use serde::Deserialize;
+
+use crate::picker::Picker;
+
+/// Everyday patterns, written for the regex crate so they work with both engines.
+const BUILTIN: [(&str, &str, &str); 17] = [
+    (
+        "ipv4",
+        r"\b(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\b",
+        "IPv4 address",
+    ),
+    (
+        "ipv6",
+        r"(?:[0-9A-Fa-f]{1,4}:){7}[0-9A-Fa-f]{1,4}|(?:[0-9A-Fa-f]{1,4}:){1,7}:|(?:[0-9A-Fa-f]{1,4}:){1,6}:[0-9A-Fa-f]{1,4}|::(?:[0-9A-Fa-f]{1,4}:){0,6}[0-9A-Fa-f]{1,4}",
+        "IPv6 address, full or with one :: (loose)",
+    ),
+    (
+        "mac",
+        r"(?:[0-9A-Fa-f]{2}[:-]){5}[0-9A-Fa-f]{2}",
+        "MAC address",
+    ),
+    (
+        "email",
+        r"[\w.+-]+@[\w-]+(?:\.[\w-]+)+",
+        "email address (loose)",
+    ),
+    (
+        "url",
+        r"https?://[\w.-]+(?::\d+)?(?:/[^\s?#]*)?(?:\?[^\s#]*)?(?:#\S*)?",
+        "http or https URL",
+    ),
+    (
+        "date",
+        r"\d{4}-(?:0[1-9]|1[0-2])-(?:0[1-9]|[12]\d|3[01])",
+        "ISO-8601 date, 2024-05-01",
+    ),
+    (
+        "datetime",
+        r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:?\d{2})?",
+        "ISO-8601 date and time, 2024-05-01T12:30:00Z",
+    ),
+    (
+        "time",
+        r"(?:[01]\d|2[0-3]):[0-5]\d(?::[0-5]\d)?",
+        "24 hour time, 12:30 or 12:30:59",
+    ),
+    (
+        "uuid",
+        r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+        "UUID",
+    ),
+    (
+        "semver",
+        r"\bv?(?<major>0|[1-9]\d*)\.(?<minor>0|[1-9]\d*)\.(?<patch>0|[1-9]\d*)(?:-(?<pre>[0-9A-Za-z.-]+))?(?:\+(?<build>[0-9A-Za-z.-]+))?\b",
+        "semantic version with named parts",
+    ),
+    (
+        "log level",
+        r"\b(?:TRACE|DEBUG|INFO|WARN(?:ING)?|ERROR|FATAL)\b",
+        "log level",
+    ),
+    (
+        "number",
+        r"[+-]?\d+(?:\.\d+)?(?:[eE][+-]?\d+)?",
+        "integer or decimal number",
+    ),
+    (
+        "hex color",
+        r"#(?:[0-9a-fA-F]{3}){1,2}\b",
+        "CSS hex color, #fff or #ffffff",
+    ),
+    (
+        "quoted string",
+        r#""(?:[^"\\]|\\.)*""#,
+        "double quoted string with escapes",
+    ),
+    ("key=value", r"(?<key>\w+)=(?<value>\S+)", "key=value pair"),
+    (
+        "trailing whitespace",
+        r"[ \t]+$",
+        "whitespace at the end of a line, with multi-line",
+    ),
+    (
+        "blank line",
+        r"(?m)^[ \t]*$",
+        "empty or whitespace only line",
+    ),
+];
+
+#[derive(Clone, Deserialize)]
+pub struct Pattern {
+    pub name: String,
+    pub regex: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Default, Deserialize)]
+struct UserLibrary {
+    #[serde(default, rename = "pattern")]
+    patterns: Vec<Pattern>,
+}
+
+/// The built-in patterns followed by the ones from `regect/library.toml` in the config
+/// directory.
+pub struct Library {
+    patterns: Vec<Pattern>,
+    /// Why the user library could not be read, if it exists.
+    error: Option<String>,
+}
+
+impl Library {
+    pub fn load() -> Self {
+        let mut patterns = BUILTIN
+            .iter()
+            .map(|(name, regex, description)| Pattern {
+                name: name.to_string(),
+                regex: regex.to_string(),
+                description: description.to_string(),
+            })
+            .collect::<Vec<_>>();
+        let mut error = None;
+        let path = dirs::config_dir().map(|dir| dir.join("regect").join("library.toml"));
+        if let Some(toml) = path.and_then(|path| fs::read_to_string(path).ok()) {
+            match toml::from_str::<UserLibrary>(&toml) {
+                Ok(user) => patterns.extend(user.patterns),
+                Err(err) => error = Some(format!("library.toml: {}", err.message())),
+            }
+        }
+        Self { patterns, error }
+    }
+
+    /// Patterns whose name or description contains all words of the query, ignoring case.
+    pub fn search(&self, query: &str) -> Vec<&Pattern> {
+        let query = query.to_lowercase();
+        self.patterns
+            .iter()
+            .filter(|p| {
+                let text = format!("{} {}", p.name, p.description).to_lowercase();
+                query.split_whitespace().all(|word| text.contains(word))
+            })
+            .collect()
+    }
+
+    /// The patterns matching the query of `browser`, the selected one in full below them as the
+    /// list only has room for the names.
+    pub fn picker<'b>(&'b self, browser: &'b Picker) -> impl Widget + 'b {
+        let patterns = self.search(browser.current_query());
+        let width = patterns.iter().map(|p| p.name.len()).max().unwrap_or(0);
+        browser
+            .widget(patterns, move |pattern| {
+                Line::from(vec![
+                    Span::raw(format!("{:<width$}  ", pattern.name, width = width)),
+                    Span::styled(
+                        pattern.description.clone(),
+                        Style::default().fg(Color::Gray),
+                    ),
+                ])
+            })
+            .detail(|pattern| {
+                Line::styled(
+                    pattern.regex.clone(),
+                    Style::default().fg(Color::LightGreen),
+                )
+            })
+            .error(self.error.as_deref())
+    }
+}
+
+/// The alt+l overlay to pick a pattern from the library.
+pub fn library_browser<'a>() -> Picker<'a> {
+    Picker::new("Library (enter insert, esc cancel)", "Search the library")
+}
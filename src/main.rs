@@ -1,6 +1,8 @@
 use std::{
-    io,
+    fs, io,
     io::{BufWriter, IsTerminal},
+    path::{Path, PathBuf},
+    process::ExitCode,
 };
 
 use ratatui::crossterm::{
@@ -12,8 +14,26 @@ use ratatui::{backend::CrosstermBackend, Terminal};
 
 mod app;
 mod banners;
+mod batch;
+mod bench;
 mod body;
+mod cli;
+mod clipboard;
+mod command;
+mod engine;
+mod explain;
+mod export;
+mod filter;
+mod flags;
+mod history;
+mod library;
+mod picker;
 mod regex_input;
+mod session;
+mod set;
+mod suite;
+mod syntax;
+mod worker;
 
 fn read_from_stdin() -> Option<Vec<String>> {
     let input = io::stdin();
@@ -24,8 +44,93 @@ fn read_from_stdin() -> Option<Vec<String>> {
     }
 }
 
-fn main() -> io::Result<()> {
-    let input = read_from_stdin();
+fn read_from_file(path: &Path) -> io::Result<Vec<String>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(String::from)
+        .collect())
+}
+
+/// A file named on the command line can not be read, exits with 2 like the other usage
+/// errors so it is told apart from failing tests or finding nothing.
+fn unreadable(path: &Path, err: io::Error) -> ExitCode {
+    eprintln!("{}: {}", path.display(), err);
+    ExitCode::from(2)
+}
+
+fn main() -> io::Result<ExitCode> {
+    let args = cli::Args::parse_given();
+    let suite = match &args.tests {
+        Some(path) if args.batch || path.exists() => match suite::Suite::load(path) {
+            Ok(suite) => Some(suite),
+            Err(err) => return Ok(unreadable(path, err)),
+        },
+        _ => None,
+    };
+    // Running the tests does not need any input.
+    if let (true, Some(suite)) = (args.batch, &suite) {
+        return Ok(batch::run_suite(suite));
+    }
+    let input = match &args.file {
+        Some(path) => match read_from_file(path) {
+            Ok(lines) => Some(lines),
+            Err(err) => return Ok(unreadable(path, err)),
+        },
+        None => read_from_stdin(),
+    };
+    let mut patterns = match &args.patterns {
+        Some(path) => match read_from_file(path) {
+            Ok(lines) => lines,
+            Err(err) => return Ok(unreadable(path, err)),
+        },
+        None => vec![],
+    };
+    // In bench mode the regex is the baseline the patterns are compared to.
+    if let (app::Mode::Bench, Some(regex)) = (args.mode, &args.regex) {
+        if !patterns.is_empty() && patterns.first() != Some(regex) {
+            patterns.insert(0, regex.clone());
+        }
+    }
+    if args.batch {
+        return Ok(batch::run(&args, patterns, input.unwrap_or_default()));
+    }
+    let mut session = match &args.session {
+        Some(path) if path.exists() => match session::Session::load(path) {
+            Ok(session) => session,
+            Err(err) => return Ok(unreadable(path, err)),
+        },
+        _ => session::Session::default(),
+    };
+    session.apply(&args, patterns, suite);
+    let history = history::History::load();
+    // The last pattern fills in what was not given with it.
+    if let (true, Some(last)) = (args.last, history.last()) {
+        session.regex = last.regex.clone();
+        if !args.given("flags") {
+            session.flags = last.flags;
+        }
+        if !args.given("engine") {
+            session.engine = last.engine;
+        }
+        if args.sub.is_none() {
+            session.substitution = last.substitution.clone();
+        }
+    }
+    if let Some(input) = input.filter(|input| !input.is_empty()) {
+        // A trailing empty line to start typing on, as when the input is typed.
+        session.body = input.join("\n") + "\n";
+    }
+    let state = app::InitialState {
+        session,
+        tests_path: args
+            .tests
+            .unwrap_or_else(|| PathBuf::from("regect-tests.toml")),
+        session_path: args
+            .session
+            .unwrap_or_else(|| PathBuf::from("regect-session.toml")),
+        history,
+    };
+
     let output = io::stderr();
     let mut output = output.lock();
 
@@ -33,7 +138,7 @@ fn main() -> io::Result<()> {
     crossterm::execute!(output, EnterAlternateScreen, EnableMouseCapture)?;
     let mut term = Terminal::new(CrosstermBackend::new(BufWriter::new(output)))?;
 
-    let mut app = app::App::new(input);
+    let mut app = app::App::new(state);
     let output = app.run(&mut term)?;
 
     disable_raw_mode()?;
@@ -48,5 +153,5 @@ fn main() -> io::Result<()> {
         println!("{}", output);
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
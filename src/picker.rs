@@ -0,0 +1,152 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Widget},
+};
+use ratatui_textarea::{Input, Key, TextArea};
+
+/// How an item is shown in the picker.
+type Display<'b, T> = Box<dyn Fn(&T) -> Line<'b> + 'b>;
+
+/// What a key did in the picker.
+pub enum Picked {
+    Cancel,
+    /// Enter on the item at this index of the results, which may be empty.
+    Item(usize),
+}
+
+/// An overlay to search a list and pick one item, like the ^r history search.
+pub struct Picker<'a> {
+    pub query: TextArea<'a>,
+    pub selected: usize,
+    title: &'static str,
+}
+
+impl Picker<'_> {
+    pub fn new(title: &'static str, placeholder: &'static str) -> Self {
+        let mut query = TextArea::default();
+        query.set_placeholder_text(placeholder);
+        query.set_cursor_line_style(Style::default());
+        Self {
+            query,
+            selected: 0,
+            title,
+        }
+    }
+
+    pub fn current_query(&self) -> &str {
+        &self.query.lines()[0]
+    }
+
+    /// Move the selection or edit the query, `results` is the number of items matching it.
+    pub fn input(&mut self, input: Input, results: usize) -> Option<Picked> {
+        match input {
+            Input { key: Key::Esc, .. } => return Some(Picked::Cancel),
+            Input {
+                key: Key::Enter, ..
+            } => return Some(Picked::Item(self.selected)),
+            Input { key: Key::Up, .. } => self.selected = self.selected.saturating_sub(1),
+            Input { key: Key::Down, .. } => {
+                self.selected = (self.selected + 1).min(results.saturating_sub(1))
+            }
+            input => {
+                if self.query.input(input) {
+                    self.selected = 0;
+                }
+            }
+        }
+        None
+    }
+
+    /// The overlay listing `items`, the results of the query, each shown as `line`.
+    pub fn widget<'b, T>(
+        &'b self,
+        items: Vec<T>,
+        line: impl Fn(&T) -> Line<'b> + 'b,
+    ) -> PickerWidget<'b, T> {
+        PickerWidget {
+            picker: self,
+            items,
+            line: Box::new(line),
+            detail: None,
+            error: None,
+        }
+    }
+}
+
+pub struct PickerWidget<'b, T> {
+    picker: &'b Picker<'b>,
+    items: Vec<T>,
+    line: Display<'b, T>,
+    detail: Option<Display<'b, T>>,
+    error: Option<&'b str>,
+}
+
+impl<'b, T> PickerWidget<'b, T> {
+    /// Show the selected item in full below the list.
+    pub fn detail(mut self, detail: impl Fn(&T) -> Line<'b> + 'b) -> Self {
+        self.detail = Some(Box::new(detail));
+        self
+    }
+
+    /// Show an error in the title, like a list that could not be read.
+    pub fn error(mut self, error: Option<&'b str>) -> Self {
+        self.error = error;
+        self
+    }
+}
+
+impl<T> Widget for PickerWidget<'_, T> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let picker = self.picker;
+        Clear.render(area, buf);
+        let mut block = Block::new()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Cyan))
+            .borders(Borders::ALL)
+            .title(picker.title);
+        if let Some(error) = self.error {
+            block = block.title(Line::styled(
+                error.to_string(),
+                Style::default().fg(Color::LightRed),
+            ));
+        }
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let mut constraints = vec![Constraint::Length(1), Constraint::Fill(1)];
+        if self.detail.is_some() {
+            constraints.push(Constraint::Length(1));
+        }
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(constraints)
+            .split(inner);
+        picker.query.render(layout[0], buf);
+
+        let lines = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let line = (self.line)(item);
+                match i == picker.selected {
+                    true => line.style(Style::default().add_modifier(Modifier::REVERSED)),
+                    false => line,
+                }
+            })
+            .skip(
+                picker
+                    .selected
+                    .saturating_sub(layout[1].height.saturating_sub(1) as usize),
+            )
+            .collect::<Vec<_>>();
+        Paragraph::new(lines).render(layout[1], buf);
+
+        if let (Some(detail), Some(item)) = (&self.detail, self.items.get(picker.selected)) {
+            Paragraph::new(detail(item)).render(layout[2], buf);
+        }
+    }
+}
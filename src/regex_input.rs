@@ -1,25 +1,522 @@
+use std::{
+    cell::Cell,
+    ops::Range,
+    time::{Duration, Instant},
+};
+
 use ratatui::{
-    style::{Color, Style},
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
     widgets::{Block, BorderType, Borders, Paragraph, Widget},
 };
-use regex::Regex;
-use ratatui_textarea::TextArea;
+use ratatui_textarea::{CursorMove, TextArea};
+
+use crate::{
+    body::get_color,
+    engine::{Engine, Program, Regex},
+    filter::Context,
+    flags::Flags,
+    set::PatternSet,
+    suite::{parse_cases, Case},
+    syntax::highlight,
+};
+
+pub enum RegexError {
+    /// The pattern does not parse, `span` is the char range of the offending part.
+    Syntax { message: String, span: Range<usize> },
+    /// The pattern is valid but compiles to a program larger than the size limit.
+    CompiledTooBig(usize),
+}
+
+impl RegexError {
+    /// Build the pattern, or explain why it does not build.
+    fn compile(pattern: &str, flags: &Flags, engine: Engine) -> Result<Regex, Self> {
+        let err = match engine.build(pattern, flags) {
+            Ok(reg) => return Ok(reg),
+            Err(err) => err,
+        };
+        if let Some(limit) = err.size_limit() {
+            return Err(Self::CompiledTooBig(limit));
+        }
+        let whole = 0..pattern.chars().count();
+        match engine {
+            Engine::Regex => {
+                let span = match flags.parser().parse(pattern) {
+                    Err(regex_syntax::Error::Parse(err)) => Some(*err.span()),
+                    Err(regex_syntax::Error::Translate(err)) => Some(*err.span()),
+                    _ => None,
+                };
+                let span = match span {
+                    Some(span) => {
+                        span.start.column - 1..(span.end.column - 1).max(span.start.column)
+                    }
+                    None => whole,
+                };
+                Err(Self::Syntax {
+                    message: err.to_string(),
+                    span,
+                })
+            }
+            // Formatted like the errors of the regex crate so both engines look the same.
+            Engine::Fancy => match err.position(pattern) {
+                Some(position) => Err(Self::Syntax {
+                    message: format!(
+                        "regex parse error:\n    {}\n    {}^\nerror: {}",
+                        pattern,
+                        " ".repeat(position),
+                        err
+                    ),
+                    span: position..position + 1,
+                }),
+                None => Err(Self::Syntax {
+                    message: format!("regex error:\nerror: {}", err),
+                    span: whole,
+                }),
+            },
+        }
+    }
+
+    fn summary(&self) -> String {
+        match self {
+            Self::Syntax { message, .. } => message
+                .lines()
+                .last()
+                .unwrap_or_default()
+                .trim_start_matches("error: ")
+                .to_string(),
+            Self::CompiledTooBig(_) => "compiled regex too big".to_string(),
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Self::Syntax { message, .. } => message.clone(),
+            Self::CompiledTooBig(limit) => format!(
+                "The compiled regex exceeds the size limit of {} bytes.\n\
+                 Try smaller counted repetitions or disable unicode (alt+u).",
+                limit
+            ),
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Self::Syntax { .. } => Color::LightRed,
+            Self::CompiledTooBig(_) => Color::LightYellow,
+        }
+    }
+}
 
 pub struct RegexInput<'a> {
     pub textarea: TextArea<'a>,
+    pub flags: Flags,
+    pub engine: Engine,
+    error: Option<RegexError>,
+    /// The pattern built with the current flags and engine, rebuilt by `validate`.
+    compiled: Option<Regex>,
+    /// How long building the pattern took.
+    compile_time: Duration,
+    program: Option<Program>,
+    /// Mirrors the horizontal scroll of the textarea so styles can be put on the right cells.
+    scroll: Cell<u16>,
 }
 
 impl RegexInput<'_> {
-    pub fn new() -> Self {
-        let mut textarea = TextArea::default();
+    pub fn new(regex: String, flags: Flags, engine: Engine) -> Self {
+        let mut textarea = TextArea::new(vec![regex]);
+        textarea.move_cursor(CursorMove::End);
         textarea.set_placeholder_text("Enter a valid regex");
+        textarea.set_cursor_line_style(Style::default());
+        textarea.set_style(Style::default().fg(Color::LightGreen));
+
+        let mut input = Self {
+            textarea,
+            flags,
+            engine,
+            error: None,
+            compiled: None,
+            compile_time: Duration::ZERO,
+            program: None,
+            scroll: Cell::new(0),
+        };
+        input.validate();
+        input
+    }
+
+    fn title(&self) -> String {
+        let title = match self.flags.letters().as_str() {
+            "" => "Regex".to_string(),
+            letters => format!("Regex ({})", letters),
+        };
+        match &self.error {
+            Some(err) => format!("{}: {}", title, err.summary()),
+            None => title,
+        }
+    }
+
+    /// Styles for char ranges of the pattern on top of the base style. With a cursor the
+    /// bracket pair at the cursor is highlighted as well.
+    fn styles(&self, cursor: Option<usize>) -> Vec<(Range<usize>, Style)> {
+        let highlighted = highlight(&self.textarea.lines()[0], self.flags.ignore_whitespace);
+        let mut styles = highlighted
+            .tokens
+            .iter()
+            .map(|(range, token)| (range.clone(), token.style()))
+            .collect::<Vec<_>>();
+        if let Some((open, close)) = cursor.and_then(|cursor| highlighted.pair_at(cursor)) {
+            let style = Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD);
+            styles.push((open..open + 1, style));
+            styles.push((close..close + 1, style));
+        }
+        if let Some(RegexError::Syntax { span, .. }) = &self.error {
+            styles.push((
+                span.clone(),
+                Style::default()
+                    .fg(Color::LightRed)
+                    .add_modifier(Modifier::UNDERLINED | Modifier::BOLD),
+            ));
+        }
+        styles
+    }
+
+    pub fn unfocused(&self) -> impl Widget + '_ {
+        let mut chars = self.textarea.lines()[0]
+            .chars()
+            .map(|c| (c, Style::default()))
+            .collect::<Vec<_>>();
+        for (range, style) in self.styles(None) {
+            for (_, s) in chars.iter_mut().take(range.end).skip(range.start) {
+                *s = s.patch(style);
+            }
+        }
+        let spans = chars
+            .into_iter()
+            .map(|(c, style)| Span::styled(c.to_string(), style))
+            .collect::<Vec<_>>();
+
+        Paragraph::new(Line::from(spans)).block(
+            Block::new()
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(match &self.error {
+                    Some(err) => err.color(),
+                    None => Color::Gray,
+                }))
+                .borders(Borders::ALL)
+                .title(self.title()),
+        )
+    }
+
+    /// The flags bar followed by the full error message, if any.
+    pub fn status(&self) -> impl Widget + '_ {
+        let mut text = Text::from(self.flags.bar());
+        if let Some(err) = &self.error {
+            for line in err.message().lines() {
+                text.push_line(Line::styled(
+                    line.to_string(),
+                    Style::default().fg(err.color()),
+                ));
+            }
+        }
+        Paragraph::new(text)
+    }
+
+    pub fn status_height(&self) -> u16 {
+        match &self.error {
+            Some(err) => 1 + err.message().lines().count() as u16,
+            None => 1,
+        }
+    }
+
+    pub fn current_regex(&self) -> Option<&Regex> {
+        self.compiled.as_ref()
+    }
+
+    pub fn compile_time(&self) -> Duration {
+        self.compile_time
+    }
+
+    pub fn program(&self) -> Option<&Program> {
+        self.program.as_ref()
+    }
+
+    pub fn current_regex_str(&self) -> String {
+        format!("{}{}", self.flags.inline(), self.textarea.lines()[0])
+    }
+
+    pub fn toggle_flag(&mut self, flag: char) {
+        if self.flags.toggle(flag) {
+            self.validate();
+        }
+    }
+
+    pub fn toggle_engine(&mut self) {
+        self.engine = self.engine.toggle();
+        self.validate();
+    }
+
+    pub fn validate(&mut self) {
+        let pattern = &self.textarea.lines()[0];
+        let started = Instant::now();
+        match RegexError::compile(pattern, &self.flags, self.engine) {
+            Ok(reg) => {
+                self.compile_time = started.elapsed();
+                // What fancy-regex runs is not the program the regex crate would compile.
+                self.program = match self.engine {
+                    Engine::Regex => Program::new(pattern, &self.flags),
+                    Engine::Fancy => None,
+                };
+                self.compiled = Some(reg);
+                self.error = None;
+            }
+            Err(err) => {
+                self.program = None;
+                self.compiled = None;
+                self.error = Some(err);
+            }
+        }
+        let color = match &self.error {
+            Some(err) => err.color(),
+            None => Color::LightGreen,
+        };
+        let mut block = Block::default()
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .title(self.title());
+        if self.error.is_some() {
+            block = block.border_style(Style::default().fg(color));
+        }
+        self.textarea.set_style(Style::default().fg(color));
+        self.textarea.set_block(block);
+    }
+}
+
+impl Widget for &RegexInput<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.textarea.render(area, buf);
+
+        // Same scrolling rule as the textarea uses to keep the cursor in view.
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        if inner.height == 0 || inner.width == 0 {
+            return;
+        }
+        let cursor = self.textarea.cursor().1 as u16;
+        let mut scroll = self.scroll.get();
+        if cursor < scroll {
+            scroll = cursor;
+        } else if scroll + inner.width <= cursor {
+            scroll = cursor + 1 - inner.width;
+        }
+        self.scroll.set(scroll);
+
+        for (range, style) in self.styles(Some(cursor as usize)) {
+            for i in range {
+                let Some(x) = (i as u16).checked_sub(scroll) else {
+                    continue;
+                };
+                if x >= inner.width {
+                    continue;
+                }
+                if let Some(cell) = buf.cell_mut((inner.x + x, inner.y)) {
+                    cell.set_style(style);
+                }
+            }
+        }
+    }
+}
+
+/// One pattern per line for set mode, sharing the flags of the regex input.
+pub struct PatternsInput<'a> {
+    pub textarea: TextArea<'a>,
+    /// Line index and error of every invalid pattern.
+    errors: Vec<(usize, RegexError)>,
+    /// The patterns built with the current flags, rebuilt by `validate`.
+    set: Option<PatternSet>,
+}
+
+impl PatternsInput<'_> {
+    pub fn new(patterns: Vec<String>, flags: &Flags, engine: Engine) -> Self {
+        let mut textarea = TextArea::new(patterns);
+        textarea.set_placeholder_text("Enter one regex per line");
+        textarea.set_cursor_line_style(Style::default());
+        textarea.set_line_number_style(Style::default().fg(Color::DarkGray));
+        let mut input = Self {
+            textarea,
+            errors: vec![],
+            set: None,
+        };
+        input.validate(flags, engine);
+        input
+    }
+
+    fn title(&self, flags: &Flags) -> String {
+        let title = match flags.letters().as_str() {
+            "" => "Patterns".to_string(),
+            letters => format!("Patterns ({})", letters),
+        };
+        match self.errors.len() {
+            0 => title,
+            1 => format!("{}: 1 invalid pattern", title),
+            n => format!("{}: {} invalid patterns", title, n),
+        }
+    }
+
+    /// Height of the box, grows with the patterns up to ten lines.
+    pub fn height(&self) -> u16 {
+        self.textarea.lines().len().clamp(1, 10) as u16 + 2
+    }
+
+    pub fn unfocused(&self, flags: &Flags) -> impl Widget + '_ {
+        let lines = self
+            .textarea
+            .lines()
+            .iter()
+            .enumerate()
+            .map(|(i, pattern)| {
+                let style = match self.errors.iter().any(|(line, _)| *line == i) {
+                    true => Style::default().fg(Color::LightRed),
+                    false => Style::default(),
+                };
+                Line::from(vec![
+                    Span::styled(format!("{:>2} ", i + 1), Style::default().fg(get_color(i))),
+                    Span::styled(pattern.clone(), style),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        Paragraph::new(lines).block(
+            Block::new()
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(match self.errors.is_empty() {
+                    true => Color::Gray,
+                    false => Color::LightRed,
+                }))
+                .borders(Borders::ALL)
+                .title(self.title(flags)),
+        )
+    }
+
+    /// The flags bar followed by the error summary of every invalid pattern.
+    pub fn status(&self, flags: &Flags) -> impl Widget + '_ {
+        let mut text = Text::from(flags.bar());
+        for (line, err) in &self.errors {
+            text.push_line(Line::styled(
+                format!("pattern {}: {}", line + 1, err.summary()),
+                Style::default().fg(err.color()),
+            ));
+        }
+        Paragraph::new(text)
+    }
+
+    pub fn status_height(&self) -> u16 {
+        1 + self.errors.len() as u16
+    }
+
+    pub fn current_set(&self) -> Option<&PatternSet> {
+        self.set.as_ref()
+    }
+
+    /// Check every pattern with the engine, the set is built with the regex crate regardless.
+    pub fn validate(&mut self, flags: &Flags, engine: Engine) {
+        self.errors = self
+            .textarea
+            .lines()
+            .iter()
+            .enumerate()
+            .filter(|(_, pattern)| !pattern.is_empty())
+            .filter_map(|(i, pattern)| {
+                RegexError::compile(pattern, flags, engine)
+                    .err()
+                    .map(|err| (i, err))
+            })
+            .collect();
+        self.set = PatternSet::new(self.textarea.lines(), flags);
+        let mut block = Block::default()
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .title(self.title(flags));
+        if !self.errors.is_empty() {
+            block = block.border_style(Style::default().fg(Color::LightRed));
+        }
+        self.textarea
+            .set_style(Style::default().fg(Color::LightGreen));
+        self.textarea.set_block(block);
+    }
+}
+
+/// The test cases of the regex, one per line.
+pub struct CasesInput<'a> {
+    pub textarea: TextArea<'a>,
+    /// Outcome of the last save, cleared on the next edit.
+    pub saved: Option<String>,
+}
+
+impl CasesInput<'_> {
+    pub fn new(cases: Vec<String>) -> Self {
+        let mut textarea = TextArea::new(cases);
+        textarea.set_placeholder_text("+ must match, - must not match, 1=\"value\" must capture");
+        textarea.set_cursor_line_style(Style::default());
+        textarea.set_line_number_style(Style::default().fg(Color::DarkGray));
+        textarea.set_style(Style::default().fg(Color::LightGreen));
+        textarea.set_block(
+            Block::default()
+                .border_type(BorderType::Rounded)
+                .borders(Borders::ALL)
+                .title("Tests (^s save)"),
+        );
+        Self {
+            textarea,
+            saved: None,
+        }
+    }
+
+    pub fn unfocused(&self) -> impl Widget + '_ {
+        let lines = self
+            .textarea
+            .lines()
+            .iter()
+            .map(|line| match line.starts_with('#') {
+                true => Line::styled(line.clone(), Style::default().fg(Color::DarkGray)),
+                false => Line::from(line.clone()),
+            })
+            .collect::<Vec<_>>();
+        Paragraph::new(lines).block(
+            Block::new()
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Gray))
+                .borders(Borders::ALL)
+                .title("Tests (^s save)"),
+        )
+    }
+
+    /// All cases, or the first line that does not parse.
+    pub fn cases(&self) -> Result<Vec<Case>, String> {
+        parse_cases(self.textarea.lines())
+            .into_iter()
+            .map(|(i, case)| case.map_err(|err| format!("line {}: {}", i + 1, err)))
+            .collect()
+    }
+}
+
+pub struct SubstitutionInput<'a> {
+    pub textarea: TextArea<'a>,
+}
+
+impl SubstitutionInput<'_> {
+    pub fn new(substitution: String) -> Self {
+        let mut textarea = TextArea::new(vec![substitution]);
+        textarea.move_cursor(CursorMove::End);
+        textarea.set_placeholder_text("Enter substitution string");
         textarea.set_style(Style::default().fg(Color::LightGreen));
 
         textarea.set_block(
             Block::default()
                 .border_type(BorderType::Rounded)
                 .borders(Borders::ALL)
-                .title("Regex"),
+                .title("Substitution"),
         );
         Self { textarea }
     }
@@ -30,59 +527,116 @@ impl RegexInput<'_> {
                 .border_type(BorderType::Rounded)
                 .border_style(Style::default().fg(Color::Gray))
                 .borders(Borders::ALL)
-                .title("Regex"),
+                .title("Substitution"),
         )
     }
 
-    pub fn current_regex(&self) -> Option<Regex> {
-        Regex::new(&self.textarea.lines()[0]).ok()
+    pub fn current_substitution(&self) -> String {
+        self.textarea.lines()[0].clone()
     }
+}
 
-    pub fn current_regex_str(&self) -> String {
-        self.textarea.lines()[0].clone()
+pub struct GroupInput<'a> {
+    pub textarea: TextArea<'a>,
+}
+
+impl GroupInput<'_> {
+    pub fn new(group: String) -> Self {
+        let mut textarea = TextArea::new(vec![group]);
+        textarea.move_cursor(CursorMove::End);
+        textarea.set_placeholder_text("Enter group number or name, empty for the whole match");
+        textarea.set_style(Style::default().fg(Color::LightGreen));
+
+        textarea.set_block(
+            Block::default()
+                .border_type(BorderType::Rounded)
+                .borders(Borders::ALL)
+                .title("Group"),
+        );
+        Self { textarea }
+    }
+
+    pub fn unfocused(&self) -> impl Widget + '_ {
+        Paragraph::new(self.textarea.lines()[0].clone()).block(
+            Block::new()
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Gray))
+                .borders(Borders::ALL)
+                .title("Group"),
+        )
+    }
+
+    pub fn current_group(&self) -> &str {
+        &self.textarea.lines()[0]
+    }
+}
+
+pub struct LimitInput<'a> {
+    pub textarea: TextArea<'a>,
+}
+
+impl LimitInput<'_> {
+    pub fn new(limit: Option<usize>) -> Self {
+        let mut textarea = TextArea::new(vec![limit.map(|l| l.to_string()).unwrap_or_default()]);
+        textarea.move_cursor(CursorMove::End);
+        textarea.set_placeholder_text("Enter maximum number of fields");
+        let mut input = Self { textarea };
+        input.validate();
+        input
+    }
+
+    pub fn unfocused(&self) -> impl Widget + '_ {
+        Paragraph::new(self.textarea.lines()[0].clone()).block(
+            Block::new()
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Gray))
+                .borders(Borders::ALL)
+                .title("Limit"),
+        )
+    }
+
+    /// The limit for `splitn`, `None` when empty or not a number.
+    pub fn current_limit(&self) -> Option<usize> {
+        self.textarea.lines()[0].trim().parse().ok()
     }
 
     pub fn validate(&mut self) {
-        if let Err(err) = Regex::new(&self.textarea.lines()[0]) {
+        let text = self.textarea.lines()[0].trim();
+        if text.is_empty() || self.current_limit().is_some() {
             self.textarea
-                .set_style(Style::default().fg(Color::LightRed));
+                .set_style(Style::default().fg(Color::LightGreen));
             self.textarea.set_block(
                 Block::default()
                     .border_type(BorderType::Rounded)
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::LightRed))
-                    .title(format!("{}", err)),
+                    .title("Limit"),
             );
         } else {
             self.textarea
-                .set_style(Style::default().fg(Color::LightGreen));
+                .set_style(Style::default().fg(Color::LightRed));
             self.textarea.set_block(
                 Block::default()
                     .border_type(BorderType::Rounded)
                     .borders(Borders::ALL)
-                    .title("Regex"),
+                    .border_style(Style::default().fg(Color::LightRed))
+                    .title("Limit: not a number"),
             );
         }
     }
 }
 
-pub struct SubstitutionInput<'a> {
+pub struct ContextInput<'a> {
     pub textarea: TextArea<'a>,
 }
 
-impl SubstitutionInput<'_> {
-    pub fn new() -> Self {
-        let mut textarea = TextArea::default();
-        textarea.set_placeholder_text("Enter substitution string");
-        textarea.set_style(Style::default().fg(Color::LightGreen));
-
-        textarea.set_block(
-            Block::default()
-                .border_type(BorderType::Rounded)
-                .borders(Borders::ALL)
-                .title("Substitution"),
-        );
-        Self { textarea }
+impl ContextInput<'_> {
+    pub fn new(context: Context) -> Self {
+        let mut textarea = TextArea::new(vec![context.to_string()]);
+        textarea.move_cursor(CursorMove::End);
+        textarea.set_placeholder_text("Enter lines of context, e.g. 2 or 1,3");
+        let mut input = Self { textarea };
+        input.validate();
+        input
     }
 
     pub fn unfocused(&self) -> impl Widget + '_ {
@@ -91,11 +645,35 @@ impl SubstitutionInput<'_> {
                 .border_type(BorderType::Rounded)
                 .border_style(Style::default().fg(Color::Gray))
                 .borders(Borders::ALL)
-                .title("Substitution"),
+                .title("Context"),
         )
     }
 
-    pub fn current_substitution(&self) -> String {
-        self.textarea.lines()[0].clone()
+    /// The lines of context before and after, none when not valid.
+    pub fn current_context(&self) -> Context {
+        self.textarea.lines()[0].parse().unwrap_or_default()
+    }
+
+    pub fn validate(&mut self) {
+        if self.textarea.lines()[0].parse::<Context>().is_ok() {
+            self.textarea
+                .set_style(Style::default().fg(Color::LightGreen));
+            self.textarea.set_block(
+                Block::default()
+                    .border_type(BorderType::Rounded)
+                    .borders(Borders::ALL)
+                    .title("Context"),
+            );
+        } else {
+            self.textarea
+                .set_style(Style::default().fg(Color::LightRed));
+            self.textarea.set_block(
+                Block::default()
+                    .border_type(BorderType::Rounded)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::LightRed))
+                    .title("Context: expected N or BEFORE,AFTER"),
+            );
+        }
     }
 }
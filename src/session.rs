@@ -0,0 +1,97 @@
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{InfoMode, Mode},
+    body::Highlight,
+    cli::Args,
+    engine::Engine,
+    filter::Context,
+    flags::Flags,
+    suite::Suite,
+};
+
+/// Everything needed to reopen regect as it was, stored as TOML. Missing keys take their
+/// defaults so a session can be written by hand.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub mode: Mode,
+    pub info_mode: InfoMode,
+    pub highlight: Highlight,
+    pub regex: String,
+    pub flags: Flags,
+    pub engine: Engine,
+    pub substitution: String,
+    pub limit: Option<usize>,
+    pub nul_separated: bool,
+    pub context: Context,
+    pub invert: bool,
+    pub line_numbers: bool,
+    pub group: String,
+    pub patterns: Vec<String>,
+    /// Timed runs of every pattern in bench mode.
+    pub runs: Option<usize>,
+    /// Test case lines as they are edited, see `suite::Case`.
+    pub cases: Vec<String>,
+    pub body: String,
+}
+
+impl Session {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        toml::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Put what is given on the command line over the session. The regex of the suite is
+    /// taken unless another one is given.
+    pub fn apply(&mut self, args: &Args, patterns: Vec<String>, suite: Option<Suite>) {
+        if let Some(suite) = suite {
+            if args.regex.is_none() {
+                self.regex = suite.regex;
+                self.flags = suite.flags;
+                self.engine = suite.engine;
+            }
+            self.cases = suite.cases.iter().map(|case| case.to_string()).collect();
+        }
+        if let Some(regex) = &args.regex {
+            self.regex = regex.clone();
+        }
+        if args.given("flags") {
+            self.flags = args.flags;
+        }
+        if args.given("engine") {
+            self.engine = args.engine;
+        }
+        if args.given("mode") {
+            self.mode = args.mode;
+        }
+        if let Some(sub) = &args.sub {
+            self.substitution = sub.clone();
+        }
+        if args.limit.is_some() {
+            self.limit = args.limit;
+        }
+        if let Some(context) = args.context {
+            self.context = context;
+        }
+        self.invert |= args.invert;
+        self.line_numbers |= args.line_numbers;
+        if let Some(group) = &args.group {
+            self.group = group.clone();
+        }
+        if args.patterns.is_some() {
+            self.patterns = patterns;
+        }
+        if args.runs.is_some() {
+            self.runs = args.runs;
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let toml =
+            toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(path, toml)
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+
+use ratatui::{
+    layout::Constraint,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, Row, Table, Widget},
+};
+use regex::{RegexSet, RegexSetBuilder};
+
+use crate::{
+    body::get_color,
+    engine::{Engine, Regex},
+    flags::Flags,
+};
+
+/// The valid patterns of a pattern list, matched at once with a `RegexSet` and one by one
+/// for the spans. Patterns are identified by their position in the list so empty and
+/// invalid lines do not shift the numbering.
+#[derive(Clone)]
+pub struct PatternSet {
+    positions: Vec<usize>,
+    set: RegexSet,
+    regexes: Vec<Regex>,
+}
+
+impl PatternSet {
+    pub fn new(patterns: &[String], flags: &Flags) -> Option<Self> {
+        let mut positions = vec![];
+        let mut regexes = vec![];
+        for (position, pattern) in patterns.iter().enumerate() {
+            if pattern.is_empty() {
+                continue;
+            }
+            if let Ok(regex) = Engine::Regex.build(pattern, flags) {
+                positions.push(position);
+                regexes.push(regex);
+            }
+        }
+        let set = RegexSetBuilder::new(positions.iter().map(|p| &patterns[*p]))
+            .case_insensitive(flags.case_insensitive)
+            .multi_line(flags.multi_line)
+            .dot_matches_new_line(flags.dot_matches_new_line)
+            .ignore_whitespace(flags.ignore_whitespace)
+            .swap_greed(flags.swap_greed)
+            .unicode(flags.unicode)
+            .crlf(flags.crlf)
+            .build()
+            .ok()?;
+        Some(Self {
+            positions,
+            set,
+            regexes,
+        })
+    }
+
+    /// Positions of the patterns matching the haystack.
+    pub fn hits(&self, haystack: &str) -> Vec<usize> {
+        self.set
+            .matches(haystack)
+            .iter()
+            .map(|i| self.positions[i])
+            .collect()
+    }
+
+    /// Positions of the patterns hitting every line, none once `stop` says so.
+    pub fn line_hits(&self, body: &str, stop: impl Fn() -> bool) -> Option<Vec<Vec<usize>>> {
+        body.lines()
+            .map(|line| (!stop()).then(|| self.hits(line)))
+            .collect()
+    }
+
+    /// Matches of all patterns colored by pattern, none once `stop` says so. Where matches
+    /// of several patterns overlap the first pattern wins and the overlap is underlined.
+    pub fn segments(
+        &self,
+        body: &str,
+        stop: impl Fn() -> bool,
+    ) -> Option<Vec<(usize, usize, Style)>> {
+        // Every match opens at its start and closes at its end, closing first at the same
+        // offset. Empty matches cover nothing.
+        let mut events = vec![];
+        for i in self.set.matches(body).iter() {
+            for m in self.regexes[i].find_iter(body).filter(|m| !m.is_empty()) {
+                if stop() {
+                    return None;
+                }
+                events.push((m.start, true, self.positions[i]));
+                events.push((m.end, false, self.positions[i]));
+            }
+        }
+        events.sort_unstable();
+
+        // The patterns with a match covering the offset, a pattern's matches do not overlap.
+        let mut covering = BTreeMap::new();
+        let mut segments = vec![];
+        let mut last = 0;
+        for (offset, opens, position) in events {
+            if let Some(first) = covering.keys().next().filter(|_| last < offset) {
+                let mut style = Style::default().fg(Color::Black).bg(get_color(*first));
+                if covering.len() > 1 {
+                    style = style.add_modifier(Modifier::UNDERLINED | Modifier::BOLD);
+                }
+                segments.push((last, offset, style));
+            }
+            match opens {
+                true => covering.insert(position, ()),
+                false => covering.remove(&position),
+            };
+            last = offset;
+        }
+        Some(segments)
+    }
+}
+
+/// Every line of the input behind the comma separated numbers of the patterns hitting
+/// it, or `-` when none does.
+pub fn classify(body: &str, set: Option<&PatternSet>) -> String {
+    let line_hits = body
+        .lines()
+        .map(|line| set.map(|set| set.hits(line)).unwrap_or_default())
+        .collect::<Vec<_>>();
+    classified(body, &line_hits)
+}
+
+/// Like `classify`, from the patterns hitting every line.
+pub fn classified(body: &str, line_hits: &[Vec<usize>]) -> String {
+    body.lines()
+        .zip(line_hits)
+        .map(|(line, hits)| {
+            let hits = match hits.is_empty() {
+                true => "-".to_string(),
+                false => hits
+                    .iter()
+                    .map(|p| (p + 1).to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            };
+            format!("{}\t{}", hits, line)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Which patterns hit every line of the input, lines without a hit are marked. Nothing is
+/// listed while the hits are not in.
+pub fn hits(body: &str, set: Option<&PatternSet>, line_hits: Option<&[Vec<usize>]>) -> impl Widget {
+    let lines = body.lines().collect::<Vec<_>>();
+    let mut misses = 0;
+    let patterns = set
+        .and_then(|set| set.positions.last())
+        .map_or(0, |last| last + 1);
+    let mut hit = vec![false; patterns];
+    let mut rows = vec![];
+    for (i, (line, positions)) in lines.iter().zip(line_hits.unwrap_or_default()).enumerate() {
+        for position in positions {
+            hit[*position] = true;
+        }
+        let cell = if positions.is_empty() {
+            misses += 1;
+            Cell::new(Span::styled("none", Style::default().fg(Color::LightRed)))
+        } else {
+            Cell::new(Line::from(
+                positions
+                    .iter()
+                    .map(|p| {
+                        Span::styled(format!("{} ", p + 1), Style::default().fg(get_color(*p)))
+                    })
+                    .collect::<Vec<_>>(),
+            ))
+        };
+        rows.push(Row::new(vec![
+            Cell::new((i + 1).to_string()).style(Style::default().fg(Color::Gray)),
+            cell,
+            Cell::new(line.to_string()),
+        ]));
+    }
+    let width = lines.len().to_string().len() as u16;
+    let unused = match (&set, line_hits) {
+        (Some(set), Some(_)) => set.positions.iter().filter(|p| !hit[**p]).count(),
+        _ => 0,
+    };
+
+    Table::new(
+        rows,
+        vec![
+            Constraint::Length(width),
+            Constraint::Percentage(20),
+            Constraint::Fill(1),
+        ],
+    )
+    .column_spacing(2)
+    .block(
+        Block::new()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Gray))
+            .borders(Borders::ALL)
+            .title(match line_hits {
+                Some(_) => format!(
+                    "Hits, {} line{} without a hit, {} pattern{} never hit",
+                    misses,
+                    if misses == 1 { "" } else { "s" },
+                    unused,
+                    if unused == 1 { "" } else { "s" }
+                ),
+                None => "Hits".to_string(),
+            }),
+    )
+}
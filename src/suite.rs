@@ -0,0 +1,228 @@
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+use ratatui::{
+    layout::Constraint,
+    style::{Color, Style},
+    widgets::{Block, BorderType, Borders, Cell, Row, Table, Widget},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{Engine, Regex},
+    flags::Flags,
+};
+
+/// A regex with the cases it has to pass, stored as TOML.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Suite {
+    pub regex: String,
+    #[serde(default)]
+    pub flags: Flags,
+    #[serde(default)]
+    pub engine: Engine,
+    #[serde(default, rename = "case")]
+    pub cases: Vec<Case>,
+}
+
+impl Suite {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        toml::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let toml =
+            toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(path, toml)
+    }
+
+    /// Check every case, failing all of them when the regex does not build.
+    pub fn run(&self) -> Vec<Result<(), String>> {
+        let reg = self.engine.build(&self.regex, &self.flags);
+        self.cases
+            .iter()
+            .map(|case| match &reg {
+                Ok(reg) => case.check(reg),
+                Err(_) => Err("invalid regex".to_string()),
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "expect", rename_all = "kebab-case")]
+pub enum Case {
+    Match {
+        input: String,
+    },
+    NoMatch {
+        input: String,
+    },
+    /// The first match has to capture `value` in `group`, a group number or name.
+    Capture {
+        input: String,
+        group: String,
+        value: String,
+    },
+}
+
+impl Case {
+    pub fn check(&self, reg: &Regex) -> Result<(), String> {
+        match self {
+            Case::Match { input } => match reg.is_match(input) {
+                true => Ok(()),
+                false => Err("no match".to_string()),
+            },
+            Case::NoMatch { input } => match reg.find_iter(input).next() {
+                Some(range) => Err(format!("matched {:?}", &input[range])),
+                None => Ok(()),
+            },
+            Case::Capture {
+                input,
+                group,
+                value,
+            } => {
+                let index = reg
+                    .group_index(group)
+                    .ok_or_else(|| format!("no group '{}'", group))?;
+                let caps = reg
+                    .captures_iter(input)
+                    .next()
+                    .ok_or_else(|| "no match".to_string())?;
+                match caps[index].clone() {
+                    Some(range) if input[range.clone()] == *value => Ok(()),
+                    Some(range) => Err(format!("group {} captured {:?}", group, &input[range])),
+                    None => Err(format!("group {} did not capture", group)),
+                }
+            }
+        }
+    }
+}
+
+/// One case per line: `+ input` must match, `- input` must not match and
+/// `group="value" input` must capture the value in the group.
+impl FromStr for Case {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = |rest: &str| rest.strip_prefix(' ').unwrap_or(rest).to_string();
+        if let Some(rest) = s.strip_prefix('+') {
+            return Ok(Case::Match { input: input(rest) });
+        }
+        if let Some(rest) = s.strip_prefix('-') {
+            return Ok(Case::NoMatch { input: input(rest) });
+        }
+        let expected = "expected `+ input`, `- input` or `group=\"value\" input`";
+        let (group, rest) = s.split_once("=\"").ok_or(expected)?;
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        loop {
+            match chars.next() {
+                Some((_, '\\')) => match chars.next() {
+                    Some((_, c)) => value.push(c),
+                    None => return Err("unterminated value".to_string()),
+                },
+                Some((i, '"')) => {
+                    return Ok(Case::Capture {
+                        input: input(&rest[i + 1..]),
+                        group: group.trim().to_string(),
+                        value,
+                    })
+                }
+                Some((_, c)) => value.push(c),
+                None => return Err("unterminated value".to_string()),
+            }
+        }
+    }
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Case::Match { input } => write!(f, "+ {}", input),
+            Case::NoMatch { input } => write!(f, "- {}", input),
+            Case::Capture {
+                input,
+                group,
+                value,
+            } => write!(
+                f,
+                "{}=\"{}\" {}",
+                group,
+                value.replace('\\', "\\\\").replace('"', "\\\""),
+                input
+            ),
+        }
+    }
+}
+
+/// The cases of the editor lines with their line index, empty lines and `#` comments are
+/// skipped.
+pub fn parse_cases(lines: &[String]) -> Vec<(usize, Result<Case, String>)> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| (i, line.parse()))
+        .collect()
+}
+
+/// The outcome of every case line, lines that do not parse fail.
+pub fn check_cases(lines: &[String], reg: Option<&Regex>) -> Vec<(usize, Result<(), String>)> {
+    parse_cases(lines)
+        .into_iter()
+        .map(|(i, case)| {
+            let outcome = match (case, reg) {
+                (Err(err), _) => Err(err),
+                (Ok(_), None) => Err("invalid regex".to_string()),
+                (Ok(case), Some(reg)) => case.check(reg),
+            };
+            (i, outcome)
+        })
+        .collect()
+}
+
+pub fn results(lines: &[String], reg: Option<&Regex>, saved: Option<&str>) -> impl Widget {
+    let outcomes = check_cases(lines, reg);
+    let passed = outcomes.iter().filter(|(_, o)| o.is_ok()).count();
+    let rows = outcomes
+        .iter()
+        .map(|(i, outcome)| {
+            let (mark, message, color) = match outcome {
+                Ok(()) => ("✓", String::new(), Color::Green),
+                Err(err) => ("✗", err.clone(), Color::LightRed),
+            };
+            Row::new(vec![
+                Cell::new((i + 1).to_string()).style(Style::default().fg(Color::Gray)),
+                Cell::new(mark).style(Style::default().fg(color)),
+                Cell::new(message),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let width = lines.len().to_string().len() as u16;
+
+    let mut title = format!("Results, {} of {} passed", passed, outcomes.len());
+    if let Some(saved) = saved {
+        title = format!("{}, {}", title, saved);
+    }
+    Table::new(
+        rows,
+        vec![
+            Constraint::Length(width),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+        ],
+    )
+    .column_spacing(1)
+    .block(
+        Block::new()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(if passed == outcomes.len() {
+                Color::Gray
+            } else {
+                Color::LightRed
+            }))
+            .borders(Borders::ALL)
+            .title(title),
+    )
+}
@@ -0,0 +1,230 @@
+use std::ops::Range;
+
+use ratatui::style::{Color, Modifier, Style};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token {
+    Group,
+    Class,
+    Quantifier,
+    Anchor,
+    Escape,
+    Alternation,
+    Comment,
+}
+
+impl Token {
+    pub fn style(&self) -> Style {
+        match self {
+            Token::Group => Style::default().fg(Color::Cyan),
+            Token::Class => Style::default().fg(Color::Yellow),
+            Token::Quantifier => Style::default().fg(Color::LightMagenta),
+            Token::Anchor => Style::default().fg(Color::LightBlue),
+            Token::Escape => Style::default().fg(Color::LightCyan),
+            Token::Alternation => Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+            Token::Comment => Style::default().fg(Color::DarkGray),
+        }
+    }
+}
+
+/// Tokens and bracket pairs of a pattern, all ranges are char indices.
+#[derive(Default)]
+pub struct Highlighted {
+    pub tokens: Vec<(Range<usize>, Token)>,
+    /// Positions of matching `(` `)` and `[` `]`.
+    pub pairs: Vec<(usize, usize)>,
+}
+
+impl Highlighted {
+    /// The bracket pair the cursor is on or directly behind.
+    pub fn pair_at(&self, cursor: usize) -> Option<(usize, usize)> {
+        let on = |i: usize| {
+            self.pairs
+                .iter()
+                .find(|(open, close)| *open == i || *close == i)
+        };
+        on(cursor)
+            .or_else(|| cursor.checked_sub(1).and_then(on))
+            .copied()
+    }
+}
+
+/// Scan the pattern for token kinds. This is lexical only so it keeps working on
+/// patterns that do not parse yet, unbalanced brackets are simply not paired.
+pub fn highlight(pattern: &str, ignore_whitespace: bool) -> Highlighted {
+    let chars = pattern.chars().collect::<Vec<_>>();
+    let mut result = Highlighted::default();
+    let mut groups = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+        let token = match chars[i] {
+            '\\' => {
+                let (end, token) = escape(&chars, i);
+                i = end;
+                Some(token)
+            }
+            '[' => {
+                i = class(&chars, i, &mut result.pairs);
+                Some(Token::Class)
+            }
+            '(' => {
+                groups.push(i);
+                i += 1;
+                if chars.get(i) == Some(&'?') {
+                    while i < chars.len() && !matches!(chars[i], ':' | ')' | '>') {
+                        i += 1;
+                    }
+                    if i < chars.len() && chars[i] != ')' {
+                        i += 1;
+                    }
+                }
+                Some(Token::Group)
+            }
+            ')' => {
+                if let Some(open) = groups.pop() {
+                    result.pairs.push((open, i));
+                }
+                i += 1;
+                Some(Token::Group)
+            }
+            '*' | '+' | '?' => {
+                i += 1;
+                Some(Token::Quantifier)
+            }
+            '{' => match counted_repetition(&chars, i) {
+                Some(end) => {
+                    i = end;
+                    Some(Token::Quantifier)
+                }
+                None => {
+                    i += 1;
+                    None
+                }
+            },
+            '^' | '$' => {
+                i += 1;
+                Some(Token::Anchor)
+            }
+            '|' => {
+                i += 1;
+                Some(Token::Alternation)
+            }
+            '.' => {
+                i += 1;
+                Some(Token::Class)
+            }
+            '#' if ignore_whitespace => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                Some(Token::Comment)
+            }
+            _ => {
+                i += 1;
+                None
+            }
+        };
+        // A `?` directly after a quantifier makes it lazy.
+        if token == Some(Token::Quantifier) && chars.get(i) == Some(&'?') {
+            i += 1;
+        }
+        if let Some(token) = token {
+            result.tokens.push((start..i, token));
+        }
+    }
+    result
+}
+
+/// The end of the escape starting at `i` and whether it is a class, an anchor or a plain escape.
+fn escape(chars: &[char], i: usize) -> (usize, Token) {
+    let Some(&c) = chars.get(i + 1) else {
+        return (i + 1, Token::Escape);
+    };
+    let end = i + 2;
+    match c {
+        'd' | 'D' | 's' | 'S' | 'w' | 'W' => (end, Token::Class),
+        'p' | 'P' => (braced(chars, end), Token::Class),
+        'A' | 'z' | '<' | '>' => (end, Token::Anchor),
+        'b' | 'B' => match chars.get(end) {
+            Some('{') => (braced(chars, end), Token::Anchor),
+            _ => (end, Token::Anchor),
+        },
+        'x' | 'u' | 'U' => match chars.get(end) {
+            Some('{') => (braced(chars, end), Token::Escape),
+            _ => {
+                let digits = match c {
+                    'x' => 2,
+                    'u' => 4,
+                    _ => 8,
+                };
+                let len = chars[end..]
+                    .iter()
+                    .take(digits)
+                    .take_while(|c| c.is_ascii_hexdigit())
+                    .count();
+                (end + len, Token::Escape)
+            }
+        },
+        _ => (end, Token::Escape),
+    }
+}
+
+/// Skip a `{...}` at `i` if there is one, or a single letter as in `\pL`.
+fn braced(chars: &[char], i: usize) -> usize {
+    match chars.get(i) {
+        Some('{') => chars[i..]
+            .iter()
+            .position(|c| *c == '}')
+            .map_or(chars.len(), |p| i + p + 1),
+        Some(_) => i + 1,
+        None => i,
+    }
+}
+
+/// The end of the bracketed class starting at `i`, nested classes are paired as well.
+fn class(chars: &[char], i: usize, pairs: &mut Vec<(usize, usize)>) -> usize {
+    let mut j = i + 1;
+    if chars.get(j) == Some(&'^') {
+        j += 1;
+    }
+    // A `]` directly after the opening bracket is a literal.
+    if chars.get(j) == Some(&']') {
+        j += 1;
+    }
+    while j < chars.len() {
+        match chars[j] {
+            '\\' => j += 2,
+            '[' if chars.get(j + 1) == Some(&':') => {
+                j = chars[j..]
+                    .windows(2)
+                    .position(|w| w == [':', ']'])
+                    .map_or(j + 1, |p| j + p + 2);
+            }
+            '[' => j = class(chars, j, pairs),
+            ']' => {
+                pairs.push((i, j));
+                return j + 1;
+            }
+            _ => j += 1,
+        }
+    }
+    chars.len()
+}
+
+/// The end of `{n}`, `{n,}` or `{n,m}` at `i`, if it is one.
+fn counted_repetition(chars: &[char], i: usize) -> Option<usize> {
+    let close = chars[i..].iter().position(|c| *c == '}')? + i;
+    let inner = chars[i + 1..close].iter().collect::<String>();
+    let valid = match inner.split_once(',') {
+        Some((min, max)) => {
+            min.trim().parse::<u32>().is_ok()
+                && (max.trim().is_empty() || max.trim().parse::<u32>().is_ok())
+        }
+        None => inner.trim().parse::<u32>().is_ok(),
+    };
+    valid.then_some(close + 1)
+}
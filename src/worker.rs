@@ -0,0 +1,307 @@
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Once,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use ratatui::style::Style;
+
+use crate::{
+    app::Mode,
+    bench::{bench, Measurement, Variant},
+    engine::{Captures, Engine, Regex},
+    filter::selected_lines,
+    set::PatternSet,
+};
+
+/// Searches still running after this long stop, the matches found so far are kept.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Name of the threads running jobs, their panics are shown in the footer.
+const THREAD: &str = "matcher";
+
+/// Everything the results depend on. The input is only known by its version, a job is
+/// started whenever this changes.
+#[derive(Clone, PartialEq)]
+pub struct Job {
+    /// The regex with its flags inline, empty in set and bench mode.
+    pub regex: String,
+    pub engine: Engine,
+    pub version: u64,
+    pub mode: Mode,
+    pub substitution: String,
+    pub limit: Option<usize>,
+    pub invert: bool,
+    /// The patterns of set and bench mode, empty in the other modes.
+    pub patterns: Vec<String>,
+    /// The flags of the patterns, inline.
+    pub flags: String,
+    pub runs: usize,
+}
+
+/// The output pane of the mode, match and extract mode work from the captures alone.
+pub enum Output {
+    None,
+    Substituted(String),
+    Fields(Vec<String>),
+    /// Whether every line of the input is selected by the filter.
+    Selected(Vec<bool>),
+    Bench(Vec<Measurement>),
+    /// The matches of all patterns styled by pattern, and the patterns hitting every line.
+    Set {
+        segments: Vec<(usize, usize, Style)>,
+        hits: Vec<Vec<usize>>,
+    },
+}
+
+pub struct Results {
+    /// Every match in the input.
+    pub captures: Vec<Captures>,
+    pub output: Output,
+    /// How long finding the matches took, the output is not counted.
+    pub elapsed: Duration,
+    /// The search ran into the timeout, the captures are partial and there is no output.
+    pub timed_out: bool,
+    /// Why there is no output, such as fancy-regex giving up on backtracking.
+    pub error: Option<String>,
+}
+
+impl Results {
+    fn failed(error: String, elapsed: Duration) -> Self {
+        Self {
+            captures: vec![],
+            output: Output::None,
+            elapsed,
+            timed_out: false,
+            error: Some(error),
+        }
+    }
+}
+
+/// Runs the regex over the input on a thread of its own, so a slow search does not hold up
+/// the keys. Starting a job cancels the one running, its results would be stale anyway.
+pub struct Worker {
+    job: Option<Job>,
+    started: Instant,
+    cancel: Arc<AtomicBool>,
+    receiver: Option<mpsc::Receiver<Results>>,
+    results: Option<Results>,
+}
+
+impl Worker {
+    pub fn new() -> Self {
+        // A panic message written over the alternate screen would garble it, the panics of
+        // the matcher threads are caught and reported as their results instead.
+        static QUIET: Once = Once::new();
+        QUIET.call_once(|| {
+            let default = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                if thread::current().name() != Some(THREAD) {
+                    default(info);
+                }
+            }));
+        });
+        Self {
+            job: None,
+            started: Instant::now(),
+            cancel: Arc::new(AtomicBool::new(false)),
+            receiver: None,
+            results: None,
+        }
+    }
+
+    /// Whether the results are, or are being, computed for the job.
+    pub fn is_current(&self, job: &Job) -> bool {
+        self.job.as_ref() == Some(job)
+    }
+
+    pub fn start(
+        &mut self,
+        job: Job,
+        regex: Option<Regex>,
+        set: Option<PatternSet>,
+        variants: Vec<Variant>,
+        body: Arc<str>,
+    ) {
+        self.cancel.store(true, Ordering::Relaxed);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        self.cancel = cancel.clone();
+        self.receiver = Some(receiver);
+        self.results = None;
+        self.started = Instant::now();
+        self.job = Some(job.clone());
+        let started = self.started;
+        let spawned = thread::Builder::new()
+            .name(THREAD.to_string())
+            .spawn(move || {
+                let results = panic::catch_unwind(AssertUnwindSafe(|| {
+                    run(
+                        &job,
+                        regex.as_ref(),
+                        set.as_ref(),
+                        &variants,
+                        &body,
+                        &cancel,
+                    )
+                }))
+                .unwrap_or_else(|panic| {
+                    Some(Results::failed(
+                        format!("matching failed, {}", message(panic.as_ref())),
+                        started.elapsed(),
+                    ))
+                });
+                if let Some(results) = results {
+                    // The receiver is gone when a newer job was started meanwhile.
+                    let _ = sender.send(results);
+                }
+            });
+        if let Err(err) = spawned {
+            self.receiver = None;
+            self.results = Some(Results::failed(
+                format!("matching failed, {}", err),
+                Duration::ZERO,
+            ));
+        }
+    }
+
+    /// Wait at most `wait` for the results of the running job, the results once they are in.
+    pub fn receive(&mut self, wait: Duration) -> Option<&Results> {
+        let results = match self.receiver.as_ref()?.recv_timeout(wait) {
+            Ok(results) => results,
+            Err(RecvTimeoutError::Timeout) => return None,
+            // Only cancelled jobs end without results and their receiver is already gone, so
+            // this thread died some other way.
+            Err(RecvTimeoutError::Disconnected) => Results::failed(
+                "matching failed without results".to_string(),
+                self.started.elapsed(),
+            ),
+        };
+        self.receiver = None;
+        self.results = Some(results);
+        self.results.as_ref()
+    }
+
+    /// The results of the current job, none while it is running.
+    pub fn results(&self) -> Option<&Results> {
+        self.results.as_ref()
+    }
+
+    /// How long the current job has been running, none when it is done.
+    pub fn running(&self) -> Option<Duration> {
+        self.receiver.as_ref().map(|_| self.started.elapsed())
+    }
+}
+
+/// The message a panic was raised with.
+fn message(panic: &(dyn Any + Send)) -> &str {
+    match panic.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => panic
+            .downcast_ref::<String>()
+            .map_or("for an unknown reason", String::as_str),
+    }
+}
+
+/// The input between the matches, the last field holding the rest when there is a limit.
+fn fields(body: &str, captures: &[Captures], limit: Option<usize>) -> Vec<String> {
+    let limit = limit.unwrap_or(usize::MAX);
+    let mut fields = vec![];
+    let mut last = 0;
+    for whole in captures.iter().filter_map(|caps| caps[0].clone()) {
+        if fields.len() + 1 >= limit {
+            break;
+        }
+        fields.push(body[last..whole.start].to_string());
+        last = whole.end;
+    }
+    if limit > 0 {
+        fields.push(body[last..].to_string());
+    }
+    fields
+}
+
+/// Collect the matches one by one, then the output, so that a cancelled job stops early.
+/// None when cancelled.
+fn run(
+    job: &Job,
+    regex: Option<&Regex>,
+    set: Option<&PatternSet>,
+    variants: &[Variant],
+    body: &str,
+    cancel: &AtomicBool,
+) -> Option<Results> {
+    let started = Instant::now();
+    let mut captures = vec![];
+    let mut timed_out = false;
+    if let Some(regex) = regex {
+        for caps in regex.captures_iter(body) {
+            if cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+            if started.elapsed() > TIMEOUT {
+                timed_out = true;
+                break;
+            }
+            captures.push(caps);
+        }
+    }
+    let elapsed = started.elapsed();
+    let stop = || cancel.load(Ordering::Relaxed) || started.elapsed() > TIMEOUT;
+    let mut error = None;
+    // None when stopped before the output was complete.
+    let output = match job.mode {
+        _ if timed_out => Some(Output::None),
+        // The output of match mode is the substitution too, as ^o prints it.
+        Mode::Match | Mode::Substitution => match regex {
+            Some(regex) => match regex.replace_until(body, &job.substitution, stop) {
+                Ok(output) => output.map(Output::Substituted),
+                Err(err) => {
+                    error = Some(err.to_string());
+                    Some(Output::None)
+                }
+            },
+            None => Some(Output::Substituted(body.to_string())),
+        },
+        Mode::Split => Some(Output::Fields(match regex {
+            Some(_) => fields(body, &captures, job.limit),
+            None => vec![body.to_string()],
+        })),
+        Mode::Filter => selected_lines(body, regex, job.invert)
+            .map(|selected| (!stop()).then_some(selected))
+            .collect::<Option<Vec<_>>>()
+            .map(Output::Selected),
+        // The runs left out when stopped are told by the number of times measured.
+        Mode::Bench => Some(Output::Bench(bench(variants, body, job.runs, stop))),
+        Mode::Set => match set {
+            Some(set) => set.segments(body, stop).and_then(|segments| {
+                Some(Output::Set {
+                    segments,
+                    hits: set.line_hits(body, stop)?,
+                })
+            }),
+            None => Some(Output::None),
+        },
+        Mode::Extract => Some(Output::None),
+    };
+    if cancel.load(Ordering::Relaxed) {
+        return None;
+    }
+    let output = output.unwrap_or_else(|| {
+        timed_out = true;
+        Output::None
+    });
+    Some(Results {
+        captures,
+        output,
+        elapsed,
+        timed_out,
+        error,
+    })
+}